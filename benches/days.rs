@@ -0,0 +1,189 @@
+use advent_of_code_2021::day22::{self, ReactorState};
+use advent_of_code_2021::day5::Vent;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The system allocator, but counting every allocation and the number of bytes allocated
+struct CountingAllocator {
+    allocations: AtomicUsize,
+    bytes: AtomicUsize,
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator {
+    allocations: AtomicUsize::new(0),
+    bytes: AtomicUsize::new(0),
+};
+
+/// Return the number of allocations and allocated bytes it takes to run `f`
+fn count_allocations<T>(f: impl FnOnce() -> T) -> (usize, usize) {
+    let allocations = ALLOCATOR.allocations.load(Ordering::Relaxed);
+    let bytes = ALLOCATOR.bytes.load(Ordering::Relaxed);
+    black_box(f());
+    (
+        ALLOCATOR.allocations.load(Ordering::Relaxed) - allocations,
+        ALLOCATOR.bytes.load(Ordering::Relaxed) - bytes,
+    )
+}
+
+/// A small xorshift generator, so generated inputs are the same for every run
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: u64) -> isize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n) as isize
+    }
+}
+
+/// The day 21 part B solution before it used a preallocated cache, kept here as a baseline
+#[derive(Default)]
+struct HashMapUniverseSplitter {
+    cache: HashMap<(usize, usize, usize, usize), (usize, usize)>,
+}
+
+impl HashMapUniverseSplitter {
+    fn num_wins(
+        &mut self,
+        p1_pos: usize,
+        p1_rem_score: usize,
+        p2_pos: usize,
+        p2_rem_score: usize,
+    ) -> (usize, usize) {
+        let mut num_p1_win = 0;
+        let mut num_p2_win = 0;
+        let rolls =
+            (1..=3).flat_map(|d1| (1..=3).flat_map(move |d2| (1..=3).map(move |d3| d1 + d2 + d3)));
+        for roll in rolls {
+            let p1_pos = (p1_pos + roll - 1) % 10 + 1;
+            let p1_rem_score = p1_rem_score.saturating_sub(p1_pos);
+            if p1_rem_score == 0 {
+                num_p1_win += 1;
+            } else {
+                let args = (p2_pos, p2_rem_score, p1_pos, p1_rem_score);
+                let (n_p2, n_p1) = self.cache.get(&args).copied().unwrap_or_else(|| {
+                    let outcomes = self.num_wins(args.0, args.1, args.2, args.3);
+                    self.cache.insert(args, outcomes);
+                    outcomes
+                });
+                num_p1_win += n_p1;
+                num_p2_win += n_p2;
+            }
+        }
+        (num_p1_win, num_p2_win)
+    }
+}
+
+fn day21(c: &mut Criterion) {
+    let dense_cache = || advent_of_code_2021::day21::part_b(black_box(4), black_box(8));
+    let hash_map_cache = || {
+        let (p1_wins, p2_wins) =
+            HashMapUniverseSplitter::default().num_wins(black_box(4), 21, black_box(8), 21);
+        p1_wins.max(p2_wins)
+    };
+
+    // Criterion only measures time, so the allocations of a single run are reported separately
+    for (name, (allocations, bytes)) in [
+        ("dense cache", count_allocations(dense_cache)),
+        ("hash map cache", count_allocations(hash_map_cache)),
+    ] {
+        println!(
+            "day21 part B/{}: {} allocations, {} bytes",
+            name, allocations, bytes
+        );
+    }
+
+    let mut group = c.benchmark_group("day21 part B");
+    group.bench_function("dense cache", |b| b.iter(dense_cache));
+    group.bench_function("hash map cache", |b| b.iter(hash_map_cache));
+    group.finish();
+}
+
+/// Generate horizontal, vertical and diagonal vents within a square of the given size
+fn generate_vents(num_vents: usize, size: u64) -> Vec<Vent> {
+    let mut rng = Rng(0x2021_1205);
+    (0..num_vents)
+        .map(|_| {
+            let start = (rng.below(size), rng.below(size));
+            let length = rng.below(size / 4);
+            let (dx, dy) = [(1, 0), (0, 1), (1, 1), (1, -1)][rng.below(4) as usize];
+            Vent {
+                start,
+                end: (start.0 + dx * length, start.1 + dy * length),
+            }
+        })
+        .collect()
+}
+
+fn day5(c: &mut Criterion) {
+    let hasher = if cfg!(feature = "rustc-hash") {
+        "FxHashMap"
+    } else {
+        "HashMap"
+    };
+    let vents = generate_vents(5000, 2000);
+    c.bench_function(&format!("day5 overlaps/{}", hasher), |b| {
+        b.iter(|| advent_of_code_2021::day5::part_b(black_box(&vents)))
+    });
+}
+
+fn day22(c: &mut Criterion) {
+    let input = std::fs::read_to_string("data/day22.txt").unwrap();
+    let steps = input
+        .lines()
+        .map(|l| day22::parse_reboot_step(l).unwrap())
+        .collect::<Vec<_>>();
+    let state = ReactorState::new(&steps);
+
+    let mut rng = Rng(0x2021_1222);
+    let points = (0..1000)
+        .map(|_| {
+            let mut coordinate = || rng.below(200_000) - 100_000;
+            (coordinate(), coordinate(), coordinate())
+        })
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("day22 is_on");
+    group.bench_function("reactor state", |b| {
+        b.iter(|| {
+            points
+                .iter()
+                .filter(|&&p| state.is_on(black_box(p)))
+                .count()
+        })
+    });
+    group.bench_function("linear scan", |b| {
+        b.iter(|| {
+            points
+                .iter()
+                .filter(|&&p| state.cubes().iter().any(|c| c.contains(black_box(p))))
+                .count()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, day5, day21, day22);
+criterion_main!(benches);
@@ -1,37 +1,38 @@
 use anyhow::Result;
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::path::Path;
 
-pub fn part_a(depths: &[usize]) -> usize {
+/// Count the number of times the sum of a sliding window of depths increases. Two consecutive
+/// windows share all depths but the first of the previous one and the last of the current one, so
+/// it's enough to compare those two.
+pub fn count_increases(depths: &[usize], window: usize) -> usize {
     depths
         .iter()
-        .copied()
-        .skip(1)
-        .zip(depths.iter().copied())
+        .skip(window)
+        .zip(depths)
         .filter(|(c, p)| c > p)
         .count()
 }
 
+pub fn part_a(depths: &[usize]) -> usize {
+    count_increases(depths, 1)
+}
+
 pub fn part_b(depths: &[usize]) -> usize {
-    let windows = depths.windows(3);
-    windows
-        .clone()
-        .skip(1)
-        .zip(windows)
-        .filter(|(c, p)| c.iter().sum::<usize>() > p.iter().sum::<usize>())
-        .count()
+    count_increases(depths, 3)
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let file = File::open(path)?;
-    let depths = io::BufReader::new(file)
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let depths = input
         .lines()
-        .map(|lr| Ok(lr?.parse::<usize>()?))
+        .map(|l| Ok(l.parse::<usize>()?))
         .collect::<Result<Vec<usize>>>()?;
     Ok((part_a(&depths), Some(part_b(&depths))))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -43,4 +44,32 @@ mod tests {
         assert_eq!(part_b(&depths), 5);
         Ok(())
     }
+
+    #[test]
+    fn test_count_increases() {
+        let depths = vec![199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+        assert_eq!(count_increases(&depths, 1), part_a(&depths));
+        assert_eq!(count_increases(&depths, 3), part_b(&depths));
+
+        // Compare with summing every window
+        for window in 1..=4 {
+            let sums = depths
+                .windows(window)
+                .map(|w| w.iter().sum::<usize>())
+                .collect::<Vec<_>>();
+            let increases = sums.windows(2).filter(|w| w[1] > w[0]).count();
+            assert_eq!(count_increases(&depths, window), increases);
+        }
+        assert_eq!(count_increases(&depths, depths.len()), 0);
+    }
+
+    #[test]
+    fn test_solve() -> Result<()> {
+        assert_eq!(
+            solve("199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n")?,
+            (7, Some(5))
+        );
+        assert!(solve("199\nabc\n").is_err());
+        Ok(())
+    }
 }
@@ -1,8 +1,37 @@
 use anyhow::{anyhow, Result};
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::collections::HashMap;
 use std::path::Path;
 
+/// The penalty for each closing bracket that is found where another one was expected
+pub const CORRUPTION_SCORES: [(char, usize); 4] = [(')', 3), (']', 57), ('}', 1197), ('>', 25137)];
+
+/// The score for each closing bracket that is needed to complete a line
+pub const COMPLETION_SCORES: [(char, usize); 4] = [(')', 1), (']', 2), ('}', 3), ('>', 4)];
+
+/// The scores used for corrupted and incomplete lines, indexed by closing bracket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scoring {
+    pub corruption: HashMap<char, usize>,
+    pub completion: HashMap<char, usize>,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self {
+            corruption: CORRUPTION_SCORES.into_iter().collect(),
+            completion: COMPLETION_SCORES.into_iter().collect(),
+        }
+    }
+}
+
+/// Look up the score for the given closing bracket in one of the score tables
+fn score(table: &HashMap<char, usize>, c: char) -> Result<usize> {
+    table
+        .get(&c)
+        .copied()
+        .ok_or_else(|| anyhow!("No score for {}", c))
+}
+
 enum SyntaxError {
     BracketMismatch(char),
     UnmatchedBrackets(Vec<char>),
@@ -39,17 +68,23 @@ fn validate_line(l: &str) -> Result<(), SyntaxError> {
     Ok(())
 }
 
-fn part_a<S: AsRef<str>>(lines: &[S]) -> Result<usize> {
+/// Find the index and offending character of the first corrupted line, if any
+pub fn first_corruption<S: AsRef<str>>(lines: &[S]) -> Option<(usize, char)> {
+    lines
+        .iter()
+        .enumerate()
+        .find_map(|(i, line)| match validate_line(line.as_ref()) {
+            Err(SyntaxError::BracketMismatch(c)) => Some((i, c)),
+            _ => None,
+        })
+}
+
+/// Sum the penalties of all corrupted lines
+pub fn part_a_with<S: AsRef<str>>(lines: &[S], scoring: &Scoring) -> Result<usize> {
     let mut penalty = 0;
     for line in lines {
         match validate_line(line.as_ref()) {
-            Err(SyntaxError::BracketMismatch(c)) => match c {
-                ')' => penalty += 3,
-                ']' => penalty += 57,
-                '}' => penalty += 1197,
-                '>' => penalty += 25137,
-                _ => unreachable!(),
-            },
+            Err(SyntaxError::BracketMismatch(c)) => penalty += score(&scoring.corruption, c)?,
             Err(SyntaxError::UnmatchedBrackets(_)) => (),
             Err(SyntaxError::InvalidCharacter(c)) => {
                 return Err(anyhow!("Invalid character {}", c))
@@ -60,7 +95,8 @@ fn part_a<S: AsRef<str>>(lines: &[S]) -> Result<usize> {
     Ok(penalty)
 }
 
-fn part_b<S: AsRef<str>>(lines: &[S]) -> Result<usize> {
+/// Find the middle score of completing all incomplete lines
+pub fn part_b_with<S: AsRef<str>>(lines: &[S], scoring: &Scoring) -> Result<usize> {
     let mut penalties = Vec::new();
     for line in lines {
         let mut penalty = 0;
@@ -74,14 +110,7 @@ fn part_b<S: AsRef<str>>(lines: &[S]) -> Result<usize> {
         };
 
         for c in unmatched_brackets {
-            penalty = 5 * penalty
-                + match c {
-                    ')' => 1,
-                    ']' => 2,
-                    '}' => 3,
-                    '>' => 4,
-                    _ => unreachable!(),
-                }
+            penalty = 5 * penalty + score(&scoring.completion, c)?;
         }
         penalties.push(penalty);
     }
@@ -89,19 +118,28 @@ fn part_b<S: AsRef<str>>(lines: &[S]) -> Result<usize> {
     Ok(penalties[penalties.len() / 2])
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let file = File::open(path)?;
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+fn part_a<S: AsRef<str>>(lines: &[S]) -> Result<usize> {
+    part_a_with(lines, &Scoring::default())
+}
+
+fn part_b<S: AsRef<str>>(lines: &[S]) -> Result<usize> {
+    part_b_with(lines, &Scoring::default())
+}
+
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let lines = input.lines().collect::<Vec<_>>();
     Ok((part_a(&lines)?, Some(part_b(&lines)?)))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const LINES: &'static [&str] = &[
+    const LINES: &[&str] = &[
         "[({(<(())[]>[[{[]{<()<>>",
         "[(()[<>])]({[<{<<[]>>(",
         "{([(<{}[<>[]}>{[]{[(<()>",
@@ -116,8 +154,45 @@ mod tests {
 
     #[test]
     fn test_part_a() -> Result<()> {
-        assert_eq!(part_a(&LINES)?, 26397);
-        assert_eq!(part_b(&LINES)?, 288957);
+        assert_eq!(part_a(LINES)?, 26397);
+        assert_eq!(part_b(LINES)?, 288957);
+        Ok(())
+    }
+
+    #[test]
+    fn test_first_corruption() {
+        assert_eq!(first_corruption(LINES), Some((2, '}')));
+        assert_eq!(first_corruption(&LINES[3..]), Some((1, ')')));
+        assert_eq!(first_corruption(&LINES[..2]), None);
+    }
+
+    #[test]
+    fn test_score_tables() {
+        assert_eq!(
+            CORRUPTION_SCORES,
+            [(')', 3), (']', 57), ('}', 1197), ('>', 25137)]
+        );
+        assert_eq!(COMPLETION_SCORES, [(')', 1), (']', 2), ('}', 3), ('>', 4)]);
+        let scoring = Scoring::default();
+        assert_eq!(score(&scoring.corruption, '}').unwrap(), 1197);
+        assert_eq!(score(&scoring.completion, '>').unwrap(), 4);
+        assert!(score(&scoring.completion, '(').is_err());
+    }
+
+    #[test]
+    fn test_custom_scoring() -> Result<()> {
+        // Make every corrupted bracket cost the same
+        let mut scoring = Scoring::default();
+        scoring.corruption.values_mut().for_each(|s| *s = 1);
+
+        // Five lines are corrupted
+        assert_eq!(part_a_with(LINES, &scoring)?, 5);
+        assert_eq!(part_a_with(LINES, &Scoring::default())?, 26397);
+        assert_eq!(part_b_with(LINES, &Scoring::default())?, 288957);
+
+        // Scores must exist for every bracket that occurs
+        scoring.corruption.remove(&'>');
+        assert!(part_a_with(LINES, &scoring).is_err());
         Ok(())
     }
 }
@@ -1,22 +1,38 @@
 use anyhow::{anyhow, Result};
 use std::collections::VecDeque;
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::path::Path;
 
-fn tick<const W: usize, const H: usize>(grid: &mut [[u8; W]; H]) -> usize {
+/// The energy level squids must exceed to flash, according to the puzzle rules
+const FLASH_THRESHOLD: u8 = 9;
+
+/// The most energy a squid can gain in a single step, one from the step itself and one from each of
+/// its eight neighbors flashing
+const MAX_ENERGY_GAIN: u8 = 9;
+
+/// Energy levels of all squids, row by row. All rows must have the same length.
+pub type Grid = Vec<Vec<u8>>;
+
+/// Advance the grid one step and return the coordinates of all squids that flashed. A squid
+/// flashes when its energy level exceeds `threshold`.
+fn tick(grid: &mut Grid, threshold: u8) -> Vec<(usize, usize)> {
     // Increment all squid timers by one
     grid.iter_mut()
         .for_each(|row| row.iter_mut().for_each(|s| *s += 1));
 
     // Detect all squids that are about to flash
-    let mut will_flash: VecDeque<_> = (0..H)
-        .flat_map(|y| (0..W).map(move |x| (x, y)))
-        .filter(|&(x, y)| grid[y][x] == 10)
+    let mut will_flash: VecDeque<_> = grid
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|&(_, &s)| s > threshold)
+                .map(move |(x, _)| (x, y))
+        })
         .collect();
 
     // While there are still squids to flash, do so
-    let mut num_flashes = 0;
+    let mut flashed = Vec::new();
     while let Some((x, y)) = will_flash.pop_front() {
         // Iterate all neighboring locations
         let neighbors = [
@@ -35,54 +51,110 @@ fn tick<const W: usize, const H: usize>(grid: &mut [[u8; W]; H]) -> usize {
         for (nx, ny) in neighbors {
             if let Some(nv) = grid.get_mut(ny).and_then(|row| row.get_mut(nx)) {
                 *nv += 1;
-                if *nv == 10 {
+                if *nv == threshold + 1 {
                     will_flash.push_back((nx, ny));
                 }
             }
         }
 
-        num_flashes += 1;
+        flashed.push((x, y));
     }
 
     // When all reactions are complete we have to reset all the squids who flashed
-    grid.iter_mut()
-        .for_each(|row| row.iter_mut().filter(|s| **s > 9).for_each(|s| *s = 0));
+    grid.iter_mut().for_each(|row| {
+        row.iter_mut()
+            .filter(|s| **s > threshold)
+            .for_each(|s| *s = 0)
+    });
 
-    num_flashes
+    flashed
+}
+
+/// Advance the grid one step using a custom flash threshold and return the coordinates of all
+/// squids that flashed. A squid flashes when its energy level exceeds `threshold`. Returns an error
+/// if the threshold or the energy levels are so high that they could overflow during the step.
+pub fn step_with_threshold(grid: &mut Grid, threshold: u8) -> Result<Vec<(usize, usize)>> {
+    // Squids never have more energy than the threshold after a step, unless they started out with
+    // more than it
+    let max_energy = grid.iter().flatten().copied().fold(threshold, u8::max);
+    if max_energy.checked_add(MAX_ENERGY_GAIN).is_none() {
+        return Err(anyhow!(
+            "Thresholds and energy levels above {} are not supported",
+            u8::MAX - MAX_ENERGY_GAIN
+        ));
+    }
+    Ok(tick(grid, threshold))
+}
+
+/// Return the coordinates of the squids that flash on the given step (starting at 0), sorted row
+/// by row
+pub fn flashed_cells(mut grid: Grid, step: usize) -> Vec<(usize, usize)> {
+    for _ in 0..step {
+        tick(&mut grid, FLASH_THRESHOLD);
+    }
+    let mut flashed = tick(&mut grid, FLASH_THRESHOLD);
+    flashed.sort_unstable_by_key(|&(x, y)| (y, x));
+    flashed
+}
+
+/// Return the energy level of every octopus after the given number of steps
+pub fn energy_after(mut grid: Grid, steps: usize) -> Grid {
+    for _ in 0..steps {
+        tick(&mut grid, FLASH_THRESHOLD);
+    }
+    grid
+}
+
+/// Return the sum of the energy levels of all octopuses
+pub fn total_energy(grid: &Grid) -> u32 {
+    grid.iter().flatten().map(|&s| u32::from(s)).sum()
 }
 
-fn part_a<const W: usize, const H: usize>(mut grid: [[u8; W]; H]) -> usize {
+fn part_a(mut grid: Grid) -> usize {
     let mut num_flashes = 0;
     for _ in 0..100 {
-        num_flashes += tick(&mut grid);
+        num_flashes += tick(&mut grid, FLASH_THRESHOLD).len();
     }
     num_flashes
 }
 
-fn part_b<const W: usize, const H: usize>(mut grid: [[u8; W]; H]) -> usize {
-    let mut num_steps = 0;
-    loop {
-        num_steps += 1;
-        if tick(&mut grid) == W * H {
-            break num_steps;
-        }
-    }
+/// Iterate over every step number (starting at 1) on which all squids flash at the same time
+pub fn sync_steps(mut grid: Grid) -> impl Iterator<Item = usize> {
+    let num_squids = grid.iter().map(Vec::len).sum::<usize>();
+    (1..).filter(move |_| tick(&mut grid, FLASH_THRESHOLD).len() == num_squids)
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    // This will panic on invalid data. Would be nice to fail more gracefully
-    let file = File::open(path)?;
-    let mut grid: [[u8; 10]; 10] = Default::default();
-    for (y, line) in io::BufReader::new(file).lines().enumerate() {
-        for (x, c) in line?.chars().enumerate() {
-            grid[y][x] = c
-                .to_digit(10)
-                .ok_or_else(|| anyhow!("{} is not a digit", c))?
-                .try_into()?;
-        }
+fn part_b(grid: Grid) -> usize {
+    sync_steps(grid).next().unwrap()
+}
+
+fn parse_grid(input: &str) -> Result<Grid> {
+    let grid = input
+        .lines()
+        .map(|line| {
+            line.chars()
+                .map(|c| {
+                    Ok(c.to_digit(10)
+                        .ok_or_else(|| anyhow!("{} is not a digit", c))?
+                        .try_into()?)
+                })
+                .collect::<Result<Vec<u8>>>()
+        })
+        .collect::<Result<Grid>>()?;
+
+    if grid.iter().any(|row| row.len() != grid[0].len()) {
+        return Err(anyhow!("All rows must have the same length"));
     }
+    Ok(grid)
+}
 
-    Ok((part_a(grid), Some(part_b(grid))))
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let grid = parse_grid(input)?;
+    Ok((part_a(grid.clone()), Some(part_b(grid))))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
 }
 
 #[cfg(test)]
@@ -102,15 +174,156 @@ mod tests {
         [5, 2, 8, 3, 7, 5, 1, 5, 2, 6],
     ];
 
+    fn example() -> Grid {
+        GRID.iter().map(|row| row.to_vec()).collect()
+    }
+
     #[test]
     fn test_part_a() -> Result<()> {
-        assert_eq!(part_a(GRID), 1656);
+        assert_eq!(part_a(example()), 1656);
         Ok(())
     }
 
     #[test]
     fn test_part_b() -> Result<()> {
-        assert_eq!(part_b(GRID), 195);
+        assert_eq!(part_b(example()), 195);
+        Ok(())
+    }
+
+    #[test]
+    fn test_flashed_cells() {
+        // Nobody flashes during the first step, but the second step causes a chain reaction
+        assert_eq!(flashed_cells(example(), 0), Vec::new());
+
+        let after_step_2 = [
+            "8807476555",
+            "5089087054",
+            "8597889608",
+            "8485769600",
+            "8700908800",
+            "6600088989",
+            "6800005943",
+            "0000007456",
+            "9000000876",
+            "8700006848",
+        ];
+        let expected = after_step_2
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '0')
+                    .map(move |(x, _)| (x, y))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(expected.len(), 35);
+        assert_eq!(flashed_cells(example(), 1), expected);
+    }
+
+    #[test]
+    fn test_flash_threshold() {
+        let count_flashes = |threshold| {
+            let mut grid = example();
+            (0..100)
+                .map(|_| tick(&mut grid, threshold).len())
+                .sum::<usize>()
+        };
+        assert_eq!(count_flashes(FLASH_THRESHOLD), 1656);
+        assert!(count_flashes(5) > count_flashes(FLASH_THRESHOLD));
+    }
+
+    #[test]
+    fn test_step_with_threshold() -> Result<()> {
+        let mut grid = example();
+        let mut expected = example();
+        assert_eq!(step_with_threshold(&mut grid, 5)?, tick(&mut expected, 5));
+        assert_eq!(grid, expected);
+
+        // Squids with the largest supported threshold eventually flash without overflowing
+        let mut grid = example();
+        let max_threshold = u8::MAX - MAX_ENERGY_GAIN;
+        let mut num_flashes = 0;
+        for _ in 0..300 {
+            num_flashes += step_with_threshold(&mut grid, max_threshold)?.len();
+        }
+        assert!(num_flashes > 0);
+
+        let mut grid = example();
+        let err = step_with_threshold(&mut grid, max_threshold + 1).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Thresholds and energy levels above 246 are not supported"
+        );
+        assert_eq!(grid, example());
+        Ok(())
+    }
+
+    #[test]
+    fn test_energy_after() -> Result<()> {
+        assert_eq!(energy_after(example(), 0), example());
+
+        let after_step_1 = parse_grid(
+            "6594254334\n3856965822\n6375667284\n7252447257\n7468496589\n\
+             5278635756\n3287952832\n7993992245\n5957959665\n6394862637\n",
+        )?;
+        assert_eq!(energy_after(example(), 1), after_step_1);
+
+        let after_step_2 = parse_grid(
+            "8807476555\n5089087054\n8597889608\n8485769600\n8700908800\n\
+             6600088989\n6800005943\n0000007456\n9000000876\n8700006848\n",
+        )?;
+        assert_eq!(energy_after(example(), 2), after_step_2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_total_energy() {
+        let mut grid = example();
+        let num_squids = grid.iter().map(Vec::len).sum::<usize>() as u32;
+        assert_eq!(num_squids, 100);
+
+        // Without flashes every step adds exactly one energy per squid
+        let before = total_energy(&grid);
+        assert!(tick(&mut grid, FLASH_THRESHOLD).is_empty());
+        assert_eq!(total_energy(&grid), before + num_squids);
+
+        // Every flash gives away at most 8 energy to its neighbors and loses at least 10 when the
+        // flashing squid is reset
+        for _ in 0..100 {
+            let before = total_energy(&grid);
+            let num_flashes = tick(&mut grid, FLASH_THRESHOLD).len() as u32;
+            assert!(
+                total_energy(&grid) + 10 * num_flashes <= before + num_squids + 8 * num_flashes
+            );
+        }
+
+        // Once synchronized every squid is reset at the same time
+        let step = sync_steps(example()).next().unwrap();
+        let grid = energy_after(example(), step);
+        assert_eq!(total_energy(&grid), 0);
+    }
+
+    #[test]
+    fn test_sync_steps() {
+        // Once synchronized every squid has the same energy level, so they keep flashing together
+        // every time they have charged up again
+        let steps = sync_steps(example()).take(4).collect::<Vec<_>>();
+        assert_eq!(steps, vec![195, 205, 215, 225]);
+    }
+
+    #[test]
+    fn test_parse_grid() -> Result<()> {
+        let grid = parse_grid(&std::fs::read_to_string("tests/data/day11_12x8.txt")?)?;
+        assert_eq!(grid.len(), 8);
+        assert!(grid.iter().all(|row| row.len() == 12));
+        assert_eq!(part_a(grid), 1620);
+
+        let answers = main(Path::new("tests/data/day11_12x8.txt"))?;
+        assert_eq!(answers, (1620, Some(153)));
+
+        assert!(parse_grid("123\n45\n").is_err());
+        assert!(parse_grid("123\n4x6\n").is_err());
         Ok(())
     }
 }
@@ -1,12 +1,10 @@
 use anyhow::{anyhow, Error as AnyhowError, Result};
 use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::path::Path;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-enum Cave {
+pub enum Cave {
     Start,
     End,
     Small(String),
@@ -16,17 +14,26 @@ enum Cave {
 impl FromStr for Cave {
     type Err = AnyhowError;
 
+    /// Cave names may contain letters and digits. The case of the letters decides whether a cave
+    /// is large or small, so they must either all be uppercase or all be lowercase. Digits are
+    /// just part of the name.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_valid_name = s.chars().all(char::is_alphanumeric);
+        let mut letters = s.chars().filter(|c| c.is_alphabetic()).peekable();
         match s {
             "start" => Ok(Self::Start),
             "end" => Ok(Self::End),
-            c if c.chars().all(char::is_uppercase) => Ok(Self::Large(c.to_string())),
-            c if c.chars().all(char::is_lowercase) => Ok(Self::Small(c.to_string())),
-            o => Err(anyhow!("Invalid cave {:?}", o)),
+            _ if !is_valid_name || letters.peek().is_none() => Err(anyhow!("Invalid cave {:?}", s)),
+            c if letters.clone().all(char::is_uppercase) => Ok(Self::Large(c.to_string())),
+            c if letters.all(char::is_lowercase) => Ok(Self::Small(c.to_string())),
+            o => Err(anyhow!("Cave {:?} mixes upper and lowercase letters", o)),
         }
     }
 }
 
+/// Reference implementation that tracks visited caves by name, see `CaveGraph` for the one used
+/// for solving
+#[cfg(test)]
 fn num_paths<T: Clone + FnMut(&Cave) -> bool>(
     connections: &HashMap<Cave, HashSet<Cave>>,
     try_visit: T,
@@ -49,29 +56,84 @@ fn num_paths<T: Clone + FnMut(&Cave) -> bool>(
         .sum()
 }
 
-fn part_a(connections: &HashMap<Cave, HashSet<Cave>>) -> usize {
-    let mut visited = HashSet::new();
-    visited.insert(Cave::Start);
-    let tracker = move |cave: &Cave| matches!(cave, Cave::Large(_)) || visited.insert(cave.clone());
-    num_paths(connections, tracker, &Cave::Start)
+/// The cave system with every cave replaced by its index in `caves`, which makes it cheap to keep
+/// track of visited caves
+struct CaveGraph {
+    caves: Vec<Cave>,
+    adjacency: Vec<Vec<usize>>,
+    start: usize,
 }
 
-fn part_b(connections: &HashMap<Cave, HashSet<Cave>>) -> usize {
-    let mut second_visit = false;
-    let mut visited = HashSet::new();
-    visited.insert(Cave::Start);
-    let tracker = move |cave: &Cave| {
-        if matches!(cave, Cave::Large(_)) || visited.insert(cave.clone()) {
-            return true;
+impl CaveGraph {
+    fn new(connections: &HashMap<Cave, HashSet<Cave>>) -> Result<Self> {
+        let caves = connections.keys().cloned().collect::<Vec<_>>();
+        if caves.len() > u64::BITS as usize {
+            return Err(anyhow!("At most {} caves are supported", u64::BITS));
         }
-
-        if cave == &Cave::Start || second_visit {
-            return false;
+        if has_large_cave_cycle(connections) {
+            return Err(anyhow!(
+                "Two large caves are connected, so there are infinitely many paths"
+            ));
         }
-        second_visit = true;
-        true
-    };
-    num_paths(connections, tracker, &Cave::Start)
+
+        let ids = caves
+            .iter()
+            .enumerate()
+            .map(|(id, cave)| (cave, id))
+            .collect::<HashMap<_, _>>();
+        let adjacency = caves
+            .iter()
+            .map(|cave| connections[cave].iter().map(|c| ids[c]).collect())
+            .collect();
+        let start = *ids
+            .get(&Cave::Start)
+            .ok_or_else(|| anyhow!("There is no start cave"))?;
+
+        Ok(Self {
+            caves,
+            adjacency,
+            start,
+        })
+    }
+
+    /// Count the paths from the start to the end cave that visit small caves at most once. If
+    /// `allow_revisit` is set, a single small cave may be visited twice
+    fn num_paths(&self, allow_revisit: bool) -> usize {
+        self.num_paths_from(self.start, 1 << self.start, allow_revisit)
+    }
+
+    /// `visited` is a bitset of the caves that have been visited so far
+    fn num_paths_from(&self, cave: usize, visited: u64, allow_revisit: bool) -> usize {
+        self.adjacency[cave]
+            .iter()
+            .map(|&next| match &self.caves[next] {
+                Cave::End => 1,
+                Cave::Start => 0,
+                Cave::Large(_) => self.num_paths_from(next, visited, allow_revisit),
+                Cave::Small(_) if visited & (1 << next) == 0 => {
+                    self.num_paths_from(next, visited | (1 << next), allow_revisit)
+                }
+                Cave::Small(_) if allow_revisit => self.num_paths_from(next, visited, false),
+                Cave::Small(_) => 0,
+            })
+            .sum()
+    }
+}
+
+/// Two large caves that are directly connected can be walked back and forth between forever, which
+/// makes the number of paths infinite
+pub fn has_large_cave_cycle(connections: &HashMap<Cave, HashSet<Cave>>) -> bool {
+    connections.iter().any(|(cave, neighbors)| {
+        matches!(cave, Cave::Large(_)) && neighbors.iter().any(|n| matches!(n, Cave::Large(_)))
+    })
+}
+
+fn part_a(connections: &HashMap<Cave, HashSet<Cave>>) -> Result<usize> {
+    Ok(CaveGraph::new(connections)?.num_paths(false))
+}
+
+fn part_b(connections: &HashMap<Cave, HashSet<Cave>>) -> Result<usize> {
+    Ok(CaveGraph::new(connections)?.num_paths(true))
 }
 
 fn parse_connections<S: AsRef<str>>(lines: &[S]) -> Result<HashMap<Cave, HashSet<Cave>>> {
@@ -93,37 +155,104 @@ fn parse_connections<S: AsRef<str>>(lines: &[S]) -> Result<HashMap<Cave, HashSet
     )
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let lines = io::BufReader::new(File::open(path)?)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let lines = input.lines().collect::<Vec<_>>();
     let paths = parse_connections(&lines)?;
-    Ok((part_a(&paths), Some(part_b(&paths))))
+    Ok((part_a(&paths)?, Some(part_b(&paths)?)))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const EXAMPLE1: &'static [&str] =
-        &["start-A", "start-b", "A-c", "A-b", "b-d", "A-end", "b-end"];
+    const EXAMPLE1: &[&str] = &["start-A", "start-b", "A-c", "A-b", "b-d", "A-end", "b-end"];
 
-    const EXAMPLE2: &'static [&str] = &[
+    const EXAMPLE2: &[&str] = &[
         "fs-end", "he-DX", "fs-he", "start-DX", "pj-DX", "end-zg", "zg-sl", "zg-pj", "pj-he",
         "RW-he", "fs-DX", "pj-RW", "zg-RW", "start-pj", "he-WI", "zg-he", "pj-fs", "start-RW",
     ];
 
     #[test]
     fn test_part_a() -> Result<()> {
-        assert_eq!(part_a(&parse_connections(EXAMPLE1)?), 10);
-        assert_eq!(part_a(&parse_connections(EXAMPLE2)?), 226);
+        assert_eq!(part_a(&parse_connections(EXAMPLE1)?)?, 10);
+        assert_eq!(part_a(&parse_connections(EXAMPLE2)?)?, 226);
         Ok(())
     }
 
     #[test]
     fn test_part_b() -> Result<()> {
-        assert_eq!(part_b(&parse_connections(EXAMPLE1)?), 36);
-        assert_eq!(part_b(&parse_connections(EXAMPLE2)?), 3509);
+        assert_eq!(part_b(&parse_connections(EXAMPLE1)?)?, 36);
+        assert_eq!(part_b(&parse_connections(EXAMPLE2)?)?, 3509);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cave_graph() -> Result<()> {
+        // The bitset based graph must agree with tracking the visited caves by name
+        for example in [EXAMPLE1, EXAMPLE2] {
+            let connections = parse_connections(example)?;
+            let graph = CaveGraph::new(&connections)?;
+
+            let mut visited = HashSet::new();
+            visited.insert(Cave::Start);
+            let once =
+                move |cave: &Cave| matches!(cave, Cave::Large(_)) || visited.insert(cave.clone());
+            assert_eq!(
+                graph.num_paths(false),
+                num_paths(&connections, once, &Cave::Start)
+            );
+
+            let mut second_visit = false;
+            let mut visited = HashSet::new();
+            visited.insert(Cave::Start);
+            let twice = move |cave: &Cave| {
+                if matches!(cave, Cave::Large(_)) || visited.insert(cave.clone()) {
+                    return true;
+                }
+                if cave == &Cave::Start || second_visit {
+                    return false;
+                }
+                second_visit = true;
+                true
+            };
+            assert_eq!(
+                graph.num_paths(true),
+                num_paths(&connections, twice, &Cave::Start)
+            );
+        }
+
+        assert!(CaveGraph::new(&parse_connections(&["a-end"])?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cave_names() -> Result<()> {
+        let connections = parse_connections(&["A1-b2"])?;
+        let large = Cave::Large("A1".to_string());
+        let small = Cave::Small("b2".to_string());
+        assert!(connections[&large].contains(&small));
+        assert!(connections[&small].contains(&large));
+
+        assert_eq!("2XY".parse::<Cave>()?, Cave::Large("2XY".to_string()));
+        assert!("12".parse::<Cave>().is_err());
+        assert!("aB".parse::<Cave>().is_err());
+        assert!("a-b".parse::<Cave>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_large_cave_cycle() -> Result<()> {
+        assert!(!has_large_cave_cycle(&parse_connections(EXAMPLE2)?));
+        assert!(has_large_cave_cycle(&parse_connections(&[
+            "start-AA", "AA-BB", "BB-end"
+        ])?));
+
+        let err = solve("start-AA\nAA-BB\nBB-end").unwrap_err();
+        assert!(err.to_string().contains("Two large caves are connected"));
         Ok(())
     }
 }
@@ -8,13 +8,14 @@ enum Fold {
     Y(isize),
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<String>)> {
-    let input = std::fs::read_to_string(path)?;
+pub type Points = HashSet<(isize, isize)>;
+
+fn parse_input(input: &str) -> Result<(Points, Vec<Fold>)> {
     let (points_str, fold_str) = input
         .split_once("\n\n")
         .ok_or_else(|| anyhow!("Unable to find folds, there should be a blank line in there"))?;
 
-    let mut points = points_str
+    let points = points_str
         .lines()
         .map(|l| {
             let (x, y) = l
@@ -22,7 +23,7 @@ pub fn main(path: &Path) -> Result<(usize, Option<String>)> {
                 .ok_or_else(|| anyhow!("No comma found for point"))?;
             Ok((x.parse()?, y.parse()?))
         })
-        .collect::<Result<HashSet<(isize, isize)>>>()?;
+        .collect::<Result<Points>>()?;
 
     let folds = fold_str
         .lines()
@@ -38,42 +39,176 @@ pub fn main(path: &Path) -> Result<(usize, Option<String>)> {
         })
         .collect::<Result<Vec<Fold>>>()?;
 
-    let mut a = None;
-    for fold in folds {
-        points = match fold {
-            Fold::X(fx) => points
-                .into_iter()
-                .map(|(x, y)| {
-                    let x = if x <= fx { x } else { 2 * fx - x };
-                    (x, y)
-                })
-                .collect::<HashSet<(isize, isize)>>(),
-            Fold::Y(fy) => points
-                .into_iter()
-                .map(|(x, y)| {
-                    let y = if y <= fy { y } else { 2 * fy - y };
-                    (x, y)
-                })
-                .collect::<HashSet<(isize, isize)>>(),
-        };
+    Ok((points, folds))
+}
 
-        if a.is_none() {
-            a = Some(points.len());
-        }
+fn apply_fold(points: Points, fold: &Fold) -> Points {
+    match *fold {
+        Fold::X(fx) => points
+            .into_iter()
+            .map(|(x, y)| {
+                let x = if x <= fx { x } else { 2 * fx - x };
+                (x, y)
+            })
+            .collect(),
+        Fold::Y(fy) => points
+            .into_iter()
+            .map(|(x, y)| {
+                let y = if y <= fy { y } else { 2 * fy - y };
+                (x, y)
+            })
+            .collect(),
     }
+}
 
+/// Draw the points using `lit` for every point and `blank` for the gaps between them
+pub fn render(points: &Points, lit: char, blank: char) -> String {
     let min_x = points.iter().map(|(x, _)| *x).min().unwrap_or(0);
     let max_x = points.iter().map(|(x, _)| *x).max().unwrap_or(0);
     let min_y = points.iter().map(|(_, y)| *y).min().unwrap_or(0);
     let max_y = points.iter().map(|(_, y)| *y).max().unwrap_or(0);
 
-    let mut b = String::new();
+    let mut output = String::new();
     for y in min_y..=max_y {
         for x in min_x..=max_x {
-            b.push(if points.contains(&(x, y)) { '#' } else { ' ' });
+            output.push(if points.contains(&(x, y)) { lit } else { blank });
         }
-        b.push('\n');
+        output.push('\n');
+    }
+    output
+}
+
+/// Apply at most `max_folds` folds, or all of them if `None`, and return the number of visible
+/// points together with the rendered paper
+pub fn fold_limited(input: &str, max_folds: Option<usize>) -> Result<(usize, String)> {
+    let (mut points, folds) = parse_input(input)?;
+    for fold in folds.iter().take(max_folds.unwrap_or(usize::MAX)) {
+        points = apply_fold(points, fold);
     }
+    Ok((points.len(), render(&points, '#', ' ')))
+}
 
-    Ok((a.unwrap(), Some(b)))
+pub fn solve(input: &str) -> Result<(usize, Option<String>)> {
+    let (mut points, folds) = parse_input(input)?;
+
+    let mut a = None;
+    for fold in &folds {
+        points = apply_fold(points, fold);
+
+        if a.is_none() {
+            a = Some(points.len());
+        }
+    }
+
+    let a = a.ok_or_else(|| anyhow!("There must be at least one fold"))?;
+    Ok((a, Some(render(&points, '#', ' '))))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<String>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Reverse;
+
+    /// Apply all folds along X before the ones along Y. Folds along the same axis are applied from
+    /// the largest position to the smallest, since a later fold must always be within the paper
+    /// that's left after the earlier ones
+    fn fold_all_sorted(mut points: Points, folds: &[Fold]) -> Points {
+        let mut folds = folds.to_vec();
+        folds.sort_by_key(|fold| match *fold {
+            Fold::X(x) => (0, Reverse(x)),
+            Fold::Y(y) => (1, Reverse(y)),
+        });
+        for fold in &folds {
+            points = apply_fold(points, fold);
+        }
+        points
+    }
+
+    fn fold_all(mut points: Points, folds: &[Fold]) -> Points {
+        for fold in folds {
+            points = apply_fold(points, fold);
+        }
+        points
+    }
+
+    const EXAMPLE: &str = "\
+6,10
+0,14
+9,10
+0,3
+10,4
+4,11
+6,0
+6,12
+4,1
+0,13
+10,12
+3,4
+3,0
+8,4
+1,10
+2,14
+8,10
+9,0
+
+fold along y=7
+fold along x=5
+";
+
+    #[test]
+    fn test_fold_limited() -> Result<()> {
+        assert_eq!(fold_limited(EXAMPLE, Some(0))?.0, 18);
+        assert_eq!(fold_limited(EXAMPLE, Some(1))?.0, 17);
+
+        let square = "#####\n#   #\n#   #\n#   #\n#####\n".to_string();
+        assert_eq!(fold_limited(EXAMPLE, None)?, (16, square.clone()));
+        assert_eq!(fold_limited(EXAMPLE, Some(5))?, (16, square.clone()));
+        assert_eq!(solve(EXAMPLE)?, (17, Some(square)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let (mut points, folds) = parse_input(EXAMPLE)?;
+        for fold in &folds {
+            points = apply_fold(points, fold);
+        }
+
+        let output = render(&points, '*', '.');
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines, ["*****", "*...*", "*...*", "*...*", "*****"]);
+        assert_eq!(
+            output.replace('*', "#").replace('.', " "),
+            render(&points, '#', ' ')
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fold_order() -> Result<()> {
+        let (points, folds) = parse_input(EXAMPLE)?;
+        let expected = fold_all(points.clone(), &folds);
+        assert_eq!(fold_all_sorted(points.clone(), &folds), expected);
+
+        // Every order of the folds gives the same paper
+        let reversed = folds.iter().rev().cloned().collect::<Vec<_>>();
+        assert_eq!(fold_all(points.clone(), &reversed), expected);
+
+        // Points on a fold line stay where they are and are never counted twice
+        let points = [(5, 0), (5, 2), (3, 0)].into_iter().collect::<Points>();
+        let folded = fold_all(points.clone(), &[Fold::X(5), Fold::Y(1)]);
+        assert_eq!(folded, [(5, 0), (3, 0)].into_iter().collect());
+        assert_eq!(folded, fold_all(points, &[Fold::Y(1), Fold::X(5)]));
+
+        let (points, folds) = parse_input(&std::fs::read_to_string("data/day13.txt")?)?;
+        assert_eq!(
+            fold_all_sorted(points.clone(), &folds),
+            fold_all(points, &folds)
+        );
+        Ok(())
+    }
 }
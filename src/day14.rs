@@ -46,33 +46,92 @@ impl PolymerExpander {
     }
 
     fn expand_template(&mut self, template: &str, depth: usize) -> HashMap<char, usize> {
-        let mut counts = HashMap::new();
-        counts.insert(template.chars().next().unwrap(), 1);
+        self.expand_template_stream(template.chars(), depth)
+    }
 
-        for (p, c) in template.chars().zip(template.chars().skip(1)) {
-            self.expand_pair(p, c, depth)
-                .into_iter()
-                .for_each(|(k, v)| *counts.entry(k).or_default() += v);
+    /// Same as `expand_template`, but only keeps the previous element of the template around, so
+    /// the template never has to be held in memory
+    fn expand_template_stream(
+        &mut self,
+        chars: impl Iterator<Item = char>,
+        depth: usize,
+    ) -> HashMap<char, usize> {
+        let mut counts = HashMap::new();
+        let mut prev = None;
+        for c in chars {
+            match prev {
+                Some(p) => self
+                    .expand_pair(p, c, depth)
+                    .into_iter()
+                    .for_each(|(k, v)| *counts.entry(k).or_default() += v),
+                None => *counts.entry(c).or_default() += 1,
+            }
+            prev = Some(c);
         }
         counts
     }
 }
 
-fn part_a(template: &str, rules: &HashMap<(char, char), char>) -> usize {
-    let mut polymer_expander = PolymerExpander::new(rules);
-    let counts = polymer_expander.expand_template(template, 10);
+/// Count how many times each element occurs after the given number of steps
+pub fn element_counts(
+    template: &str,
+    rules: &HashMap<(char, char), char>,
+    steps: usize,
+) -> HashMap<char, usize> {
+    PolymerExpander::new(rules).expand_template(template, steps)
+}
 
-    let most_common = counts.values().copied().max().unwrap();
-    let least_common = counts.values().copied().min().unwrap();
+/// Same as `element_counts`, but reads the template one element at a time so it never has to be
+/// held in memory
+pub fn element_counts_stream(
+    chars: impl Iterator<Item = char>,
+    rules: &HashMap<(char, char), char>,
+    steps: usize,
+) -> HashMap<char, usize> {
+    PolymerExpander::new(rules).expand_template_stream(chars, steps)
+}
+
+/// Return every element and its count after the given number of steps, with the most common
+/// element first. Elements with the same count are ordered alphabetically.
+pub fn sorted_counts(
+    template: &str,
+    rules: &HashMap<(char, char), char>,
+    steps: usize,
+) -> Vec<(char, usize)> {
+    let mut counts = element_counts(template, rules, steps)
+        .into_iter()
+        .collect::<Vec<_>>();
+    counts.sort_unstable_by_key(|&(c, n)| (std::cmp::Reverse(n), c));
+    counts
+}
+
+/// Return the most and least common element, and their counts, after the given number of steps.
+/// Ties are broken by picking the largest element.
+pub fn extreme_elements(
+    template: &str,
+    rules: &HashMap<(char, char), char>,
+    steps: usize,
+) -> ((char, usize), (char, usize)) {
+    let counts = element_counts(template, rules, steps);
+
+    let most_common = counts.iter().max_by_key(|&(&c, &n)| (n, c)).unwrap();
+    let least_common = counts
+        .iter()
+        .min_by_key(|&(&c, &n)| (n, std::cmp::Reverse(c)))
+        .unwrap();
+    (
+        (*most_common.0, *most_common.1),
+        (*least_common.0, *least_common.1),
+    )
+}
+
+fn part_a(template: &str, rules: &HashMap<(char, char), char>) -> usize {
+    let ((_, most_common), (_, least_common)) = extreme_elements(template, rules, 10);
     most_common - least_common
 }
 
 fn part_b(template: &str, rules: &HashMap<(char, char), char>) -> usize {
-    let mut polymer_expander = PolymerExpander::new(rules);
-    let counts = polymer_expander.expand_template(template, 40);
-
-    let most_common = counts.values().copied().max().unwrap();
-    let least_common = counts.values().copied().min().unwrap();
+    let ((_, most_common), (_, least_common)) = extreme_elements(template, rules, 40);
     most_common - least_common
 }
 
@@ -87,8 +146,7 @@ fn parse_insertion_rule(rule: &str) -> Option<((char, char), char)> {
     ))
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let input = std::fs::read_to_string(path)?;
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
     let (template, rules_str) = input
         .split_once("\n\n")
         .ok_or_else(|| anyhow!("Unable to find insertion rules"))?;
@@ -101,13 +159,15 @@ pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
     Ok((part_a(template, &rules), Some(part_b(template, &rules))))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_example() -> Result<()> {
-        let template = "NNCB";
+    fn example_rules() -> HashMap<(char, char), char> {
         let mut rules = HashMap::new();
         rules.insert(('C', 'H'), 'B');
         rules.insert(('H', 'H'), 'N');
@@ -125,10 +185,62 @@ mod tests {
         rules.insert(('B', 'C'), 'B');
         rules.insert(('C', 'C'), 'N');
         rules.insert(('C', 'N'), 'C');
+        rules
+    }
 
+    #[test]
+    fn test_example() -> Result<()> {
+        let template = "NNCB";
+        let rules = example_rules();
         assert_eq!(part_a(template, &rules), 1588);
         assert_eq!(part_b(template, &rules), 2188189693529);
 
         Ok(())
     }
+
+    #[test]
+    fn test_extreme_elements() {
+        assert_eq!(
+            extreme_elements("NNCB", &example_rules(), 10),
+            (('B', 1749), ('H', 161))
+        );
+    }
+
+    #[test]
+    fn test_sorted_counts() {
+        let counts = sorted_counts("NNCB", &example_rules(), 10);
+        assert_eq!(counts.len(), 4);
+        assert_eq!(counts.first(), Some(&('B', 1749)));
+        assert_eq!(counts.last(), Some(&('H', 161)));
+        assert_eq!(
+            sorted_counts("NNCB", &example_rules(), 0),
+            [('N', 2), ('B', 1), ('C', 1)]
+        );
+    }
+
+    #[test]
+    fn test_expand_template_stream() {
+        let rules = example_rules();
+        for steps in [0, 1, 10, 40] {
+            let mut expander = PolymerExpander::new(&rules);
+            let streamed = expander.expand_template_stream("NNCB".chars(), steps);
+            assert_eq!(streamed, element_counts("NNCB", &rules, steps));
+        }
+
+        let mut expander = PolymerExpander::new(&rules);
+        let counts = expander.expand_template_stream("NNCB".chars(), 10);
+        assert_eq!((counts[&'B'], counts[&'H']), (1749, 161));
+        assert!(expander.expand_template_stream("".chars(), 10).is_empty());
+    }
+
+    #[test]
+    fn test_element_counts_stream() {
+        let rules = example_rules();
+        let template = std::iter::repeat_n("NNCB", 100).flat_map(str::chars);
+        assert_eq!(
+            element_counts_stream(template, &rules, 10),
+            element_counts(&"NNCB".repeat(100), &rules, 10)
+        );
+        assert!(element_counts_stream(std::iter::empty(), &rules, 10).is_empty());
+    }
 }
@@ -1,93 +1,104 @@
+use crate::search;
+use crate::sparse_map::{MapPoint, SparseMap};
 use anyhow::{anyhow, Result};
-use std::cmp::Reverse;
-use std::collections::{BinaryHeap, HashMap};
-use std::fs::File;
-use std::io::{self, BufRead};
+use std::collections::HashMap;
 use std::path::Path;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-struct Coordinate {
-    x: isize,
-    y: isize,
+pub struct Coordinate {
+    pub x: isize,
+    pub y: isize,
 }
 
 impl Coordinate {
-    fn new(x: isize, y: isize) -> Self {
+    pub fn new(x: isize, y: isize) -> Self {
         Self { x, y }
     }
+}
+
+impl MapPoint for Coordinate {
+    fn from_xy(x: isize, y: isize) -> Self {
+        Self::new(x, y)
+    }
 
-    fn iter_neighbors(&self) -> impl Iterator<Item = Self> {
-        [
-            Self::new(self.x, self.y - 1),
-            Self::new(self.x + 1, self.y),
-            Self::new(self.x, self.y + 1),
-            Self::new(self.x - 1, self.y),
-        ]
-        .into_iter()
+    fn x(&self) -> isize {
+        self.x
+    }
+
+    fn y(&self) -> isize {
+        self.y
     }
 }
 
-fn lowest_risk(
-    map: &HashMap<Coordinate, usize>,
-    start: Coordinate,
-    end: Coordinate,
-) -> Option<usize> {
-    if !map.contains_key(&start) {
-        return None;
-    }
-    let mut lowest_risk = HashMap::new();
-    lowest_risk.insert(start, 0usize);
-
-    let mut to_visit = BinaryHeap::new();
-    to_visit.push(Reverse((0, start)));
-
-    while let Some(Reverse((risk, cell))) = to_visit.pop() {
-        for (neighbor, neighbor_risk) in cell
-            .iter_neighbors()
-            .filter_map(|n| map.get(&n).map(|r| (n, r + risk)))
-        {
-            if let Some(curr_lowest_risk) = lowest_risk.get_mut(&neighbor) {
-                if *curr_lowest_risk <= neighbor_risk {
-                    continue;
-                }
-                *curr_lowest_risk = neighbor_risk;
-            } else {
-                lowest_risk.insert(neighbor, neighbor_risk);
-            }
-            to_visit.push(Reverse((neighbor_risk, neighbor)));
+/// A map of risk levels that can answer several path queries without being rebuilt
+#[derive(Debug, Clone)]
+pub struct RiskMap {
+    risks: SparseMap<usize, Coordinate>,
+}
+
+impl RiskMap {
+    pub fn new(risks: HashMap<Coordinate, usize>) -> Self {
+        Self {
+            risks: risks.into_iter().collect(),
         }
     }
 
-    lowest_risk.get(&end).copied()
-}
+    /// The bottom right corner of the map, or `None` if the map is empty
+    pub fn bottom_right(&self) -> Option<Coordinate> {
+        self.risks.bounds().map(|(_, bottom_right)| bottom_right)
+    }
 
-fn enlarge_map(map: &HashMap<Coordinate, usize>, factor: isize) -> HashMap<Coordinate, usize> {
-    if map.is_empty() {
-        return map.clone();
+    pub fn contains(&self, c: Coordinate) -> bool {
+        self.risks.contains(c)
     }
 
-    let mut new_map = HashMap::new();
-    let width = map.keys().map(|c| c.x).max().unwrap() + 1;
-    let height = map.keys().map(|c| c.y).max().unwrap() + 1;
+    fn neighbors(&self, &c: &Coordinate) -> Vec<(Coordinate, usize)> {
+        self.risks.neighbors(c).map(|(n, &r)| (n, r)).collect()
+    }
 
-    for dy in 0..factor {
-        for dx in 0..factor {
-            for (c, risk) in map.iter() {
-                new_map.insert(
-                    Coordinate::new(c.x + width * dx, c.y + height * dy),
-                    (risk + dx as usize + dy as usize - 1) % 9 + 1,
-                );
+    pub fn lowest_risk(&self, start: Coordinate, end: Coordinate) -> Option<usize> {
+        if !self.risks.contains(start) {
+            return None;
+        }
+        search::dijkstra(start, |&c| c == end, |c| self.neighbors(c))
+    }
+
+    /// Find the lowest total risk from `start` to every reachable position in one go
+    pub fn distances_from(&self, start: Coordinate) -> HashMap<Coordinate, usize> {
+        if !self.risks.contains(start) {
+            return HashMap::new();
+        }
+        search::dijkstra_all(start, |c| self.neighbors(c))
+    }
+
+    /// Tile the map `factor` times in each direction, increasing the risk for every step away
+    /// from the original tile
+    pub fn enlarge(&self, factor: isize) -> Self {
+        let (width, height) = match self.bottom_right() {
+            Some(c) => (c.x + 1, c.y + 1),
+            None => return self.clone(),
+        };
+
+        let mut risks = SparseMap::new();
+        for dy in 0..factor {
+            for dx in 0..factor {
+                for (c, risk) in self.risks.iter() {
+                    risks.insert(
+                        Coordinate::new(c.x + width * dx, c.y + height * dy),
+                        (risk + dx as usize + dy as usize - 1) % 9 + 1,
+                    );
+                }
             }
         }
+        Self { risks }
     }
-    new_map
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let mut map: HashMap<Coordinate, usize> = HashMap::new();
-    for (y, line) in io::BufReader::new(File::open(path)?).lines().enumerate() {
-        for (x, c) in line?.chars().enumerate() {
-            map.insert(
+fn parse_risk_map(input: &str) -> Result<RiskMap> {
+    let mut risks = HashMap::new();
+    for (y, line) in input.lines().enumerate() {
+        for (x, c) in line.chars().enumerate() {
+            risks.insert(
                 Coordinate::new(x.try_into()?, y.try_into()?),
                 c.to_digit(10)
                     .ok_or_else(|| anyhow!("Invalid digit {:?}", c))?
@@ -95,19 +106,116 @@ pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
             );
         }
     }
-    let large_map = enlarge_map(&map, 5);
+    Ok(RiskMap::new(risks))
+}
 
-    let end = Coordinate::new(
-        map.keys().map(|c| c.x).max().unwrap(),
-        map.keys().map(|c| c.y).max().unwrap(),
-    );
-    let a = lowest_risk(&map, Coordinate::new(0, 0), end).unwrap();
+/// Find the lowest risk between two positions, defaulting to the top left and bottom right corner
+fn lowest_risk_between(
+    map: &RiskMap,
+    start: Option<Coordinate>,
+    end: Option<Coordinate>,
+) -> Result<usize> {
+    let start = start.unwrap_or_else(|| Coordinate::new(0, 0));
+    let end = match end {
+        Some(end) => end,
+        None => map
+            .bottom_right()
+            .ok_or_else(|| anyhow!("There is no path through the map"))?,
+    };
 
-    let end = Coordinate::new(
-        large_map.keys().map(|c| c.x).max().unwrap(),
-        large_map.keys().map(|c| c.y).max().unwrap(),
-    );
-    let b = lowest_risk(&large_map, Coordinate::new(0, 0), end).unwrap();
+    for c in [start, end] {
+        if !map.contains(c) {
+            return Err(anyhow!("{:?} is not within the map", c));
+        }
+    }
+
+    map.lowest_risk(start, end)
+        .ok_or_else(|| anyhow!("No path from {:?} to {:?}", start, end))
+}
 
+/// Solve both parts using custom start and end positions. The positions are the same for both
+/// the original and the enlarged map.
+pub fn solve_between(
+    input: &str,
+    start: Option<Coordinate>,
+    end: Option<Coordinate>,
+) -> Result<(usize, Option<usize>)> {
+    let map = parse_risk_map(input)?;
+    let a = lowest_risk_between(&map, start, end)?;
+    let b = lowest_risk_between(&map.enlarge(5), start, end)?;
     Ok((a, Some(b)))
 }
+
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    solve_between(input, None, None)
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+1163751742
+1381373672
+2136511328
+3694931569
+7463417111
+1319128137
+1359912421
+3125421639
+1293138521
+2311944581
+";
+
+    #[test]
+    fn test_example() -> Result<()> {
+        assert_eq!(solve(EXAMPLE)?, (40, Some(315)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_distances_from() -> Result<()> {
+        let map = parse_risk_map(EXAMPLE)?;
+        let origin = Coordinate::new(0, 0);
+        let corner = map.bottom_right().unwrap();
+
+        let distances = map.distances_from(origin);
+        assert_eq!(distances.len(), 100);
+        assert_eq!(distances[&origin], 0);
+        assert_eq!(Some(distances[&corner]), map.lowest_risk(origin, corner));
+        assert_eq!(distances[&corner], 40);
+        Ok(())
+    }
+
+    #[test]
+    fn test_solve_between() -> Result<()> {
+        let map = parse_risk_map(EXAMPLE)?;
+        let c = Coordinate::new;
+        assert_eq!(lowest_risk_between(&map, Some(c(5, 5)), None)?, 16);
+        assert_eq!(lowest_risk_between(&map, None, Some(c(2, 0)))?, 7);
+
+        // Positions outside of the original map can't be used since part A would have no path
+        assert!(solve_between(EXAMPLE, Some(c(5, 5)), None)?.0 < 40);
+        assert!(solve_between(EXAMPLE, Some(c(10, 0)), None).is_err());
+        assert!(solve_between(EXAMPLE, None, Some(c(-1, 0))).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unreachable_end() {
+        // The bottom right corner is not connected to the rest of the map
+        let c = Coordinate::new;
+        let map = RiskMap::new([(c(0, 0), 1), (c(1, 0), 1), (c(3, 3), 1)].into());
+        assert_eq!(map.lowest_risk(c(0, 0), c(3, 3)), None);
+
+        let err = lowest_risk_between(&map, None, None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No path from Coordinate { x: 0, y: 0 } to Coordinate { x: 3, y: 3 }"
+        );
+    }
+}
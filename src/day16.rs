@@ -23,6 +23,14 @@ impl VarInt {
         loop {
             let (i, is_last) = map(take(1usize), |b: u8| b == 0)(input)?;
             let (i, half_byte): (_, u128) = take(4usize)(i)?;
+
+            // Shifting would silently drop the top bits, so treat that as a decode failure
+            if out >> (u128::BITS - 4) != 0 {
+                return Err(nom::Err::Failure(nom::error::Error::new(
+                    input,
+                    nom::error::ErrorKind::TooLarge,
+                )));
+            }
             input = i;
 
             out <<= 4;
@@ -97,7 +105,7 @@ impl PacketType {
 }
 
 #[derive(Debug, Clone)]
-struct Packet {
+pub struct Packet {
     version: u8,
     body: PacketType,
 }
@@ -155,7 +163,92 @@ impl Packet {
         Ok((input, Self { version, body }))
     }
 
-    fn decode(input: &[u8]) -> Result<Packet, nom::Err<nom::error::Error<Vec<u8>>>> {
+    /// The number of immediate sub-packets of this packet
+    pub fn child_count(&self) -> usize {
+        match &self.body {
+            PacketType::Sum(p)
+            | PacketType::Product(p)
+            | PacketType::Minimum(p)
+            | PacketType::Maximum(p) => p.len(),
+            PacketType::Literal(_) => 0,
+            PacketType::GreaterThan(_) | PacketType::LessThan(_) | PacketType::EqualTo(_) => 2,
+        }
+    }
+
+    /// The maximum nesting depth of the packet, where a literal has a depth of 1
+    pub fn depth(&self) -> usize {
+        1 + match &self.body {
+            PacketType::Sum(p)
+            | PacketType::Product(p)
+            | PacketType::Minimum(p)
+            | PacketType::Maximum(p) => p.iter().map(Self::depth).max().unwrap_or(0),
+            PacketType::Literal(_) => 0,
+            PacketType::GreaterThan(op) | PacketType::LessThan(op) | PacketType::EqualTo(op) => {
+                op.0.depth().max(op.1.depth())
+            }
+        }
+    }
+
+    /// Return this packet and every packet nested inside of it, parents before their children
+    pub fn flatten(&self) -> Vec<&Packet> {
+        let mut packets = vec![self];
+        match &self.body {
+            PacketType::Sum(sp)
+            | PacketType::Product(sp)
+            | PacketType::Minimum(sp)
+            | PacketType::Maximum(sp) => packets.extend(sp.iter().flat_map(Self::flatten)),
+            PacketType::Literal(_) => {}
+            PacketType::GreaterThan(op) | PacketType::LessThan(op) | PacketType::EqualTo(op) => {
+                packets.extend(op.0.flatten());
+                packets.extend(op.1.flatten());
+            }
+        }
+        packets
+    }
+
+    /// Return the highest version of this packet or any packet nested inside of it
+    pub fn max_version(&self) -> u8 {
+        self.flatten().iter().map(|p| p.version).max().unwrap()
+    }
+
+    /// Evaluate the packet, also returning a description and the value of every packet in the
+    /// order they were evaluated. Sub-packets are always evaluated before their parent.
+    pub fn evaluate_traced(&self) -> (u128, Vec<(String, u128)>) {
+        let mut trace = Vec::new();
+        let value = self.evaluate_into(&mut trace);
+        (value, trace)
+    }
+
+    fn evaluate_into(&self, trace: &mut Vec<(String, u128)>) -> u128 {
+        let mut values = |sp: &[Packet]| {
+            sp.iter()
+                .map(|p| p.evaluate_into(trace))
+                .collect::<Vec<_>>()
+        };
+        let (description, value) = match &self.body {
+            PacketType::Sum(sp) => ("sum", values(sp).into_iter().sum()),
+            PacketType::Product(sp) => ("product", values(sp).into_iter().product()),
+            PacketType::Minimum(sp) => ("minimum", values(sp).into_iter().min().unwrap()),
+            PacketType::Maximum(sp) => ("maximum", values(sp).into_iter().max().unwrap()),
+            PacketType::Literal(VarInt(v)) => ("literal", *v),
+            PacketType::GreaterThan(op) => {
+                let (a, b) = (op.0.evaluate_into(trace), op.1.evaluate_into(trace));
+                ("greater than", (a > b) as u128)
+            }
+            PacketType::LessThan(op) => {
+                let (a, b) = (op.0.evaluate_into(trace), op.1.evaluate_into(trace));
+                ("less than", (a < b) as u128)
+            }
+            PacketType::EqualTo(op) => {
+                let (a, b) = (op.0.evaluate_into(trace), op.1.evaluate_into(trace));
+                ("equal to", (a == b) as u128)
+            }
+        };
+        trace.push((description.to_string(), value));
+        value
+    }
+
+    pub fn decode(input: &[u8]) -> Result<Packet, nom::Err<nom::error::Error<Vec<u8>>>> {
         bits(terminated(
             Self::decode_bits,
             pair(opt(many0(tag(0, 1usize))), eof),
@@ -166,17 +259,11 @@ impl Packet {
 }
 
 fn part_a(packet: &Packet) -> usize {
-    usize::from(packet.version)
-        + match &packet.body {
-            PacketType::Sum(sp)
-            | PacketType::Product(sp)
-            | PacketType::Minimum(sp)
-            | PacketType::Maximum(sp) => sp.iter().map(part_a).sum(),
-            PacketType::Literal(_) => 0,
-            PacketType::GreaterThan(op) | PacketType::LessThan(op) | PacketType::EqualTo(op) => {
-                part_a(&op.0) + part_a(&op.1)
-            }
-        }
+    packet
+        .flatten()
+        .iter()
+        .map(|p| usize::from(p.version))
+        .sum()
 }
 
 fn part_b(packet: &Packet) -> u128 {
@@ -192,19 +279,53 @@ fn part_b(packet: &Packet) -> u128 {
     }
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<u128>)> {
-    let hex_string = std::fs::read_to_string(path)?;
-    let bytes = hex_string
+/// Convert a hex string to bytes, ignoring any whitespace in it
+fn parse_hex(input: &str) -> Result<Vec<u8>> {
+    let nibbles = input
         .chars()
-        .step_by(2)
-        .zip(hex_string.chars().skip(1).step_by(2))
-        .map(|(high, low)| Ok((from_hex(high)? << 4) | from_hex(low)?))
+        .filter(|c| !c.is_whitespace())
+        .map(from_hex)
         .collect::<Result<Vec<_>>>()?;
+    if nibbles.len() % 2 != 0 {
+        return Err(anyhow!("Hex string must contain an even number of digits"));
+    }
+    Ok(nibbles
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect())
+}
 
+/// Convert a hex string without any whitespace to bytes. Unlike `parse_hex` this works on the raw
+/// bytes of the string and reports the index of the first invalid digit.
+pub fn decode_hex_fast(input: &str) -> Result<Vec<u8>> {
+    let digits = input.as_bytes();
+    if !digits.len().is_multiple_of(2) {
+        return Err(anyhow!("Hex string must contain an even number of digits"));
+    }
+
+    let nibble = |i: usize| {
+        char::from(digits[i])
+            .to_digit(16)
+            .map(|d| d as u8)
+            .ok_or_else(|| anyhow!("Invalid hex digit at index {}", i))
+    };
+    let mut bytes = Vec::with_capacity(digits.len() / 2);
+    for i in (0..digits.len()).step_by(2) {
+        bytes.push((nibble(i)? << 4) | nibble(i + 1)?);
+    }
+    Ok(bytes)
+}
+
+pub fn solve(input: &str) -> Result<(usize, Option<u128>)> {
+    let bytes = parse_hex(input)?;
     let packet = Packet::decode(&bytes)?;
     Ok((part_a(&packet), Some(part_b(&packet))))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<u128>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -275,4 +396,162 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_parse_hex() -> Result<()> {
+        assert_eq!(parse_hex("C200B40A82")?, [0xc2, 0x00, 0xb4, 0x0a, 0x82]);
+        assert_eq!(parse_hex(" C2 00 B4\n0A82\n")?, parse_hex("C200B40A82")?);
+        assert_eq!(solve("C2 00 B4 0A 82\n")?, solve("C200B40A82")?);
+        assert!(parse_hex("C20").is_err());
+        assert!(parse_hex("C2G0").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_hex_fast() -> Result<()> {
+        assert_eq!(
+            decode_hex_fast("9c0141080250320f1802104a08")?,
+            decode_hex_fast("9C0141080250320F1802104A08")?
+        );
+        assert_eq!(decode_hex_fast("c200b40a82")?, parse_hex("C200B40A82")?);
+        assert!(decode_hex_fast("")?.is_empty());
+        assert!(decode_hex_fast("C20").is_err());
+        assert_eq!(
+            decode_hex_fast("C2G0").unwrap_err().to_string(),
+            "Invalid hex digit at index 2"
+        );
+        assert_eq!(
+            decode_hex_fast("C2 0").unwrap_err().to_string(),
+            "Invalid hex digit at index 2"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_child_count() -> Result<()> {
+        // The minimum of 7, 8 and 9
+        let packet = Packet::decode(&[0x88, 0x00, 0x86, 0xc3, 0xe8, 0x81, 0x12])?;
+        assert_eq!(packet.child_count(), 3);
+
+        // 1 + 3 = 2 * 2
+        let packet = Packet::decode(&[
+            0x9c, 0x01, 0x41, 0x08, 0x02, 0x50, 0x32, 0x0f, 0x18, 0x02, 0x10, 0x4a, 0x08,
+        ])?;
+        assert_eq!(packet.child_count(), 2);
+
+        assert_eq!(Packet::decode(&literal_of_nibbles(3))?.child_count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_flatten() -> Result<()> {
+        // The minimum of 7, 8 and 9
+        let packet = Packet::decode(&parse_hex("880086C3E88112")?)?;
+        let packets = packet.flatten();
+        assert_eq!(packets.len(), 4);
+        assert_eq!(packets[0].child_count(), 3);
+        assert!(packets[1..].iter().all(|p| p.child_count() == 0));
+
+        // Children are listed right after their parent
+        let packet = Packet::decode(&parse_hex("9C0141080250320F1802104A08")?)?;
+        let child_counts = packet
+            .flatten()
+            .iter()
+            .map(|p| p.child_count())
+            .collect::<Vec<_>>();
+        assert_eq!(child_counts, [2, 2, 0, 0, 2, 0, 0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_version() -> Result<()> {
+        assert_eq!(Packet::decode(&parse_hex("D2FE28")?)?.max_version(), 6);
+
+        // The operator has version 1 while its children have version 6 and 2
+        assert_eq!(
+            Packet::decode(&parse_hex("38006F45291200")?)?.max_version(),
+            6
+        );
+
+        // The operator has version 7 while its children have version 2, 4 and 1
+        assert_eq!(
+            Packet::decode(&parse_hex("EE00D40C823060")?)?.max_version(),
+            7
+        );
+
+        // Versions 4, 1, 5 and 6 from the outermost packet to the literal
+        assert_eq!(
+            Packet::decode(&parse_hex("8A004A801A8002F478")?)?.max_version(),
+            6
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_depth() -> Result<()> {
+        assert_eq!(Packet::decode(&literal_of_nibbles(3))?.depth(), 1);
+        assert_eq!(Packet::decode(&parse_hex("C200B40A82")?)?.depth(), 2);
+
+        // An operator containing an operator containing an operator containing a literal
+        assert_eq!(
+            Packet::decode(&parse_hex("8A004A801A8002F478")?)?.depth(),
+            4
+        );
+
+        // Only the deepest child counts
+        assert_eq!(
+            Packet::decode(&parse_hex("9C0141080250320F1802104A08")?)?.depth(),
+            3
+        );
+        Ok(())
+    }
+
+    /// Encode a literal packet with version 0 consisting of the given number of 0xf nibbles
+    fn literal_of_nibbles(num_nibbles: usize) -> Vec<u8> {
+        let mut bits = vec![0, 0, 0, 1, 0, 0];
+        for i in 0..num_nibbles {
+            bits.push((i + 1 < num_nibbles) as u8);
+            bits.extend([1, 1, 1, 1]);
+        }
+        bits.chunks(8)
+            .map(|c| {
+                c.iter()
+                    .chain(std::iter::repeat(&0))
+                    .take(8)
+                    .fold(0, |byte, b| (byte << 1) | b)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_literal_overflow() -> Result<()> {
+        assert_eq!(part_b(&Packet::decode(&literal_of_nibbles(1))?), 0xf);
+        assert_eq!(part_b(&Packet::decode(&literal_of_nibbles(32))?), u128::MAX);
+        assert!(Packet::decode(&literal_of_nibbles(33)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_evaluate_traced() -> Result<()> {
+        // 1 + 2
+        let packet = Packet::decode(&parse_hex("C200B40A82")?)?;
+        let (value, trace) = packet.evaluate_traced();
+        assert_eq!(value, 3);
+        assert_eq!(
+            trace,
+            [
+                ("literal".to_string(), 1),
+                ("literal".to_string(), 2),
+                ("sum".to_string(), 3)
+            ]
+        );
+
+        // 1 + 3 = 2 * 2
+        let packet = Packet::decode(&parse_hex("9C0141080250320F1802104A08")?)?;
+        let (value, trace) = packet.evaluate_traced();
+        assert_eq!(value, part_b(&packet));
+        assert_eq!(trace.len(), 7);
+        assert_eq!(trace.last(), Some(&("equal to".to_string(), 1)));
+        Ok(())
+    }
 }
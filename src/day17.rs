@@ -48,8 +48,57 @@ fn part_b(target_x: &RangeInclusive<isize>, target_y: &RangeInclusive<isize>) ->
         .count()
 }
 
-pub fn main(path: &Path) -> Result<(isize, Option<usize>)> {
-    let input = std::fs::read_to_string(path)?;
+/// Simulate the probe step by step and check if it's ever within the target
+fn hits(
+    (mut vx, mut vy): (isize, isize),
+    target_x: &RangeInclusive<isize>,
+    target_y: &RangeInclusive<isize>,
+) -> bool {
+    let (mut x, mut y) = (0, 0);
+    loop {
+        x += vx;
+        y += vy;
+        vx -= vx.signum();
+        vy -= 1;
+        if target_x.contains(&x) && target_y.contains(&y) {
+            return true;
+        }
+
+        // Once the probe is below the target and falling it can never come back up
+        if y < *target_y.start() && vy < 0 {
+            return false;
+        }
+    }
+}
+
+/// Reference implementation of `part_b` that simulates every velocity that could possibly reach
+/// the target. Unlike `part_b` it works for targets anywhere, but it's a lot slower.
+pub fn part_b_bruteforce(
+    target_x: &RangeInclusive<isize>,
+    target_y: &RangeInclusive<isize>,
+) -> usize {
+    // Any faster and the probe passes the target on the first step. When shooting upwards it
+    // passes every height twice with the same speed, so the same holds for Y
+    let max_x = target_x.start().abs().max(target_x.end().abs());
+    let max_y = target_y.start().abs().max(target_y.end().abs());
+    (-max_y..=max_y)
+        .flat_map(|vy| (-max_x..=max_x).map(move |vx| (vx, vy)))
+        .filter(|&v| hits(v, target_x, target_y))
+        .count()
+}
+
+/// Same as `part_a`, but for a target given as its individual bounds. The highest point doesn't
+/// depend on the X bounds, as long as some X velocity can stop within them.
+pub fn part_a_bounds(_x_min: isize, _x_max: isize, y_min: isize, _y_max: isize) -> isize {
+    part_a(y_min)
+}
+
+/// Same as `part_b`, but for a target given as its individual bounds
+pub fn part_b_bounds(x_min: isize, x_max: isize, y_min: isize, y_max: isize) -> usize {
+    part_b(&(x_min..=x_max), &(y_min..=y_max))
+}
+
+pub fn solve(input: &str) -> Result<(isize, Option<usize>)> {
     let re = Regex::new(r"^target area: x=(-?\d+)\.\.(-?\d+), y=(-?\d+)..(-?\d+)$").unwrap();
     let captures = re
         .captures(input.trim_end())
@@ -66,6 +115,10 @@ pub fn main(path: &Path) -> Result<(isize, Option<usize>)> {
     ))
 }
 
+pub fn main(path: &Path) -> Result<(isize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -83,4 +136,33 @@ mod tests {
         assert_eq!(part_b(&target_x, &target_y), 112);
         Ok(())
     }
+
+    #[test]
+    fn test_bounds() {
+        assert_eq!(part_a_bounds(20, 30, -10, -5), 45);
+        assert_eq!(part_b_bounds(20, 30, -10, -5), 112);
+    }
+
+    #[test]
+    fn test_part_b_bruteforce() -> Result<()> {
+        let target_x = 20..=30isize;
+        let target_y = -10..=-5isize;
+        assert_eq!(part_b_bruteforce(&target_x, &target_y), 112);
+        assert_eq!(part_b_bruteforce(&(-30..=-20), &target_y), 112);
+
+        let input = std::fs::read_to_string("data/day17.txt")?;
+        let re = Regex::new(r"x=(-?\d+)\.\.(-?\d+), y=(-?\d+)..(-?\d+)").unwrap();
+        let bounds = re.captures(&input).unwrap();
+        let bound = |i: usize| bounds[i].parse::<isize>();
+        let (target_x, target_y) = (bound(1)?..=bound(2)?, bound(3)?..=bound(4)?);
+        assert_eq!(
+            part_b_bruteforce(&target_x, &target_y),
+            part_b(&target_x, &target_y)
+        );
+
+        // The optimized version doesn't support targets above Y: 0 yet. The only way to reach
+        // (1, 1) is to move there on the first step, since X never changes once it's 1
+        assert_eq!(part_b_bruteforce(&(1..=1), &(1..=1)), 1);
+        Ok(())
+    }
 }
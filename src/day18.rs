@@ -2,24 +2,24 @@ use anyhow::{anyhow, Result};
 use core::fmt;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::one_of;
-use nom::combinator::{map, map_res, recognize};
+use nom::character::complete::{multispace0, one_of};
+use nom::combinator::{all_consuming, map, map_res, recognize};
 use nom::multi::many1;
 use nom::sequence::{delimited, separated_pair};
 use nom::IResult;
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-enum SnailfishNumber {
+pub enum SnailfishNumber {
     Nested(Box<SnailfishNumber>, Box<SnailfishNumber>),
     Literal(usize),
 }
 
 #[derive(Debug)]
 enum Explode {
-    Unchanged(SnailfishNumber),
+    Unchanged,
     Changed(SnailfishNumber),
     ApplyBoth(usize, usize),
     ApplyLeft(usize, SnailfishNumber),
@@ -28,7 +28,7 @@ enum Explode {
 
 #[derive(Debug)]
 enum Split {
-    Unchanged(SnailfishNumber),
+    Unchanged,
     Changed(SnailfishNumber),
 }
 
@@ -67,7 +67,7 @@ impl SnailfishNumber {
     fn explode(&self) -> Option<Self> {
         match self.explode_inner(0) {
             Explode::Changed(n) | Explode::ApplyLeft(_, n) | Explode::ApplyRight(n, _) => Some(n),
-            Explode::Unchanged(_) => None,
+            Explode::Unchanged => None,
             Explode::ApplyBoth(_, _) => panic!(),
         }
     }
@@ -82,7 +82,7 @@ impl SnailfishNumber {
                         if depth >= 4 {
                             Explode::ApplyBoth(*left, *right)
                         } else {
-                            Explode::Unchanged(self.clone())
+                            Explode::Unchanged
                         }
                     }
                     (left, right) => {
@@ -98,7 +98,7 @@ impl SnailfishNumber {
                                 Explode::Changed(Self::nested(n, right.apply_left(r)))
                             }
                             Explode::Changed(n) => Explode::Changed(Self::nested(n, right.clone())),
-                            Explode::Unchanged(_) => {
+                            Explode::Unchanged => {
                                 // Since the left side was unchanged we go down the right
                                 match right.explode_inner(depth + 1) {
                                     Explode::ApplyBoth(l, r) => Explode::ApplyRight(
@@ -114,24 +114,21 @@ impl SnailfishNumber {
                                     Explode::Changed(n) => {
                                         Explode::Changed(Self::nested(left.clone(), n))
                                     }
-                                    Explode::Unchanged(_) => Explode::Unchanged(Self::nested(
-                                        left.clone(),
-                                        right.clone(),
-                                    )),
+                                    Explode::Unchanged => Explode::Unchanged,
                                 }
                             }
                         }
                     }
                 }
             }
-            n => Explode::Unchanged(n.clone()),
+            Self::Literal(_) => Explode::Unchanged,
         }
     }
 
     fn split(&self) -> Option<Self> {
         match self.split_inner() {
             Split::Changed(n) => Some(n),
-            Split::Unchanged(_) => None,
+            Split::Unchanged => None,
         }
     }
 
@@ -144,38 +141,45 @@ impl SnailfishNumber {
                 if let Split::Changed(n) = right.split_inner() {
                     return Split::Changed(Self::nested(left.as_ref().clone(), n));
                 }
-                Split::Unchanged(Self::nested(left.as_ref().clone(), right.as_ref().clone()))
+                Split::Unchanged
             }
             Self::Literal(n) => {
                 if *n >= 10 {
                     Split::Changed(Self::literal_pair(n / 2, n - n / 2))
                 } else {
-                    Split::Unchanged(Self::Literal(*n))
+                    Split::Unchanged
                 }
             }
         }
     }
 
+    /// Apply a single explode, or a split if nothing can explode. Returns `None` if the number is
+    /// already reduced
+    fn reduce_step(&self) -> Option<Self> {
+        self.explode().or_else(|| self.split())
+    }
+
+    /// Iterate over the number after each explode and split operation, ending with the reduced
+    /// number. If the number is already reduced it's yielded as is.
+    pub fn reduce_steps(&self) -> impl Iterator<Item = Self> {
+        let first = self.reduce_step();
+        let unchanged = first.is_none().then(|| self.clone());
+        std::iter::successors(first, Self::reduce_step).chain(unchanged)
+    }
+
     fn reduce(&self) -> Self {
-        let mut num = self.clone();
-        loop {
-            if let Some(n) = num.explode() {
-                num = n;
-                continue;
-            }
-            if let Some(n) = num.split() {
-                num = n;
-                continue;
-            }
-            break;
-        }
-        num
+        self.reduce_steps().last().unwrap()
     }
 
-    fn from_str(input: &str) -> Result<Self> {
-        parse_snailfish_number(input)
-            .map(|(_, n)| n)
-            .map_err(|_| anyhow!("Invalid snailfish number"))
+    /// Reduce the number, also returning how many explode and split operations that were applied
+    pub fn reduce_counted(&self) -> (Self, usize) {
+        let mut num = self.clone();
+        let mut num_operations = 0;
+        while let Some(n) = num.reduce_step() {
+            num = n;
+            num_operations += 1;
+        }
+        (num, num_operations)
     }
 
     fn add(&self, other: &Self) -> Self {
@@ -189,6 +193,20 @@ impl SnailfishNumber {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Invalid snailfish number {0:?}")]
+pub struct ParseSnailfishNumberError(String);
+
+impl FromStr for SnailfishNumber {
+    type Err = ParseSnailfishNumberError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        all_consuming(parse_snailfish_number)(input)
+            .map(|(_, n)| n)
+            .map_err(|_| ParseSnailfishNumberError(input.to_owned()))
+    }
+}
+
 impl fmt::Display for SnailfishNumber {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -198,14 +216,19 @@ impl fmt::Display for SnailfishNumber {
     }
 }
 
+/// Match the given tag, ignoring any whitespace around it
+fn padded_tag<'a>(t: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    delimited(multispace0, tag(t), multispace0)
+}
+
 fn parse_snailfish_number(input: &str) -> IResult<&str, SnailfishNumber> {
     delimited(
-        tag("["),
+        padded_tag("["),
         map(
-            separated_pair(parse_snailfish_part, tag(","), parse_snailfish_part),
+            separated_pair(parse_snailfish_part, padded_tag(","), parse_snailfish_part),
             |(a, b)| SnailfishNumber::nested(a, b),
         ),
-        tag("]"),
+        padded_tag("]"),
     )(input)
 }
 
@@ -222,6 +245,27 @@ fn parse_snailfish_part(input: &str) -> IResult<&str, SnailfishNumber> {
     ))(input)
 }
 
+/// Add the numbers in the given index order, reducing after each addition, and return the magnitude
+/// of the final sum
+pub fn magnitude_of_sum(nums: &[SnailfishNumber], order: &[usize]) -> Result<usize> {
+    let mut seen = vec![false; nums.len()];
+    for &i in order {
+        match seen.get_mut(i) {
+            Some(s) if !*s => *s = true,
+            Some(_) => return Err(anyhow!("Index {} occurs more than once in order", i)),
+            None => return Err(anyhow!("Index {} is out of bounds", i)),
+        }
+    }
+    if order.len() != nums.len() {
+        return Err(anyhow!("Order must contain every index exactly once"));
+    }
+
+    let ordered_nums = order.iter().map(|&i| nums[i].clone()).collect::<Vec<_>>();
+    Ok(SnailfishNumber::sum(&ordered_nums)
+        .map(|n| n.magnitude())
+        .unwrap_or(0))
+}
+
 fn part_a(nums: &[SnailfishNumber]) -> usize {
     SnailfishNumber::sum(nums)
         .map(|n| SnailfishNumber::magnitude(&n))
@@ -238,15 +282,19 @@ fn part_b(nums: &[SnailfishNumber]) -> usize {
     max
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let nums = io::BufReader::new(File::open(path)?)
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let nums = input
         .lines()
-        .map(|lr| SnailfishNumber::from_str(&lr?))
-        .collect::<Result<Vec<SnailfishNumber>>>()?;
+        .map(SnailfishNumber::from_str)
+        .collect::<Result<Vec<SnailfishNumber>, _>>()?;
 
     Ok((part_a(&nums), Some(part_b(&nums))))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -265,6 +313,40 @@ mod tests {
         for n in nums {
             assert_eq!(&format!("{}", SnailfishNumber::from_str(n)?), n);
         }
+
+        // Whitespace is allowed around brackets and commas, but isn't kept when displaying
+        let spaced = SnailfishNumber::from_str("[ 1 , 2 ]")?;
+        assert_eq!(spaced, SnailfishNumber::from_str("[1,2]")?);
+        assert_eq!(spaced.to_string(), "[1,2]");
+        assert_eq!(
+            SnailfishNumber::from_str(" [[1,\n 9], [ 8,5 ] ]\n")?.to_string(),
+            "[[1,9],[8,5]]"
+        );
+        assert!(SnailfishNumber::from_str("[1 2]").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_str() -> Result<()> {
+        assert_eq!(
+            "[1,2]".parse::<SnailfishNumber>()?,
+            SnailfishNumber::literal_pair(1, 2)
+        );
+
+        let nums = ["[1,2]", "[[3,4],5]"]
+            .iter()
+            .map(|n| n.parse())
+            .collect::<Result<Vec<SnailfishNumber>, _>>()?;
+        assert_eq!(nums.len(), 2);
+
+        assert_eq!(
+            "[1;2]".parse::<SnailfishNumber>(),
+            Err(ParseSnailfishNumberError("[1;2]".to_owned()))
+        );
+        assert_eq!(
+            "[1,2]garbage".parse::<SnailfishNumber>(),
+            Err(ParseSnailfishNumberError("[1,2]garbage".to_owned()))
+        );
         Ok(())
     }
 
@@ -350,6 +432,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_reduce_counted() -> Result<()> {
+        let num = SnailfishNumber::from_str("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]")?;
+
+        // Explode, explode, split, split and then explode again
+        let (reduced, num_operations) = num.reduce_counted();
+        assert_eq!(reduced, num.reduce());
+        assert_eq!(num_operations, 5);
+        assert_eq!(num.reduce_counted().1, num_operations);
+
+        assert_eq!(reduced.reduce_counted(), (reduced.clone(), 0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reduce_steps() -> Result<()> {
+        let num = SnailfishNumber::from_str("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]")?;
+        let steps = num.reduce_steps().collect::<Vec<_>>();
+        assert_eq!(steps.len(), 5);
+        assert_eq!(steps.last(), Some(&num.reduce()));
+        assert_eq!(
+            steps[0],
+            SnailfishNumber::from_str("[[[[0,7],4],[7,[[8,4],9]]],[1,1]]")?
+        );
+
+        // A reduced number is its own final step
+        let reduced = num.reduce();
+        assert_eq!(reduced.reduce_steps().collect::<Vec<_>>(), [reduced]);
+        Ok(())
+    }
+
     #[test]
     fn test_add() -> Result<()> {
         assert_eq!(
@@ -418,4 +531,124 @@ mod tests {
         assert_eq!(part_b(input), 3993);
         Ok(())
     }
+
+    #[test]
+    fn test_magnitude_of_sum() -> Result<()> {
+        let input = &[
+            SnailfishNumber::from_str("[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]")?,
+            SnailfishNumber::from_str("[[[5,[2,8]],4],[5,[[9,9],0]]]")?,
+            SnailfishNumber::from_str("[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]")?,
+            SnailfishNumber::from_str("[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]")?,
+            SnailfishNumber::from_str("[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]")?,
+            SnailfishNumber::from_str("[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]")?,
+            SnailfishNumber::from_str("[[[[5,4],[7,7]],8],[[8,3],8]]")?,
+            SnailfishNumber::from_str("[[9,3],[[9,9],[6,[4,9]]]]")?,
+            SnailfishNumber::from_str("[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]")?,
+            SnailfishNumber::from_str("[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]")?,
+        ];
+        assert_eq!(
+            magnitude_of_sum(input, &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9])?,
+            4140
+        );
+        assert_eq!(
+            magnitude_of_sum(input, &[9, 8, 7, 6, 5, 4, 3, 2, 1, 0])?,
+            4149
+        );
+
+        // This is the pair with the largest magnitude from part B
+        let pair = &[input[0].clone(), input[8].clone()];
+        assert_eq!(magnitude_of_sum(pair, &[1, 0])?, 3993);
+        assert!(magnitude_of_sum(input, &[0, 1, 2]).is_err());
+        assert!(magnitude_of_sum(input, &[0, 0, 1, 2, 3, 4, 5, 6, 7, 8]).is_err());
+        assert!(magnitude_of_sum(input, &[0, 1, 2, 3, 4, 5, 6, 7, 8, 10]).is_err());
+        Ok(())
+    }
+
+    /// A small xorshift generator, so the randomized tests are reproducible without extra
+    /// dependencies
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> usize {
+            (self.next() % n) as usize
+        }
+    }
+
+    /// Generate a random number where no pair is nested inside more than `max_depth` pairs
+    fn random_number(rng: &mut Rng, depth: usize, max_depth: usize) -> SnailfishNumber {
+        if depth > 0 && (depth > max_depth || rng.below(3) == 0) {
+            SnailfishNumber::Literal(rng.below(20))
+        } else {
+            SnailfishNumber::nested(
+                random_number(rng, depth + 1, max_depth),
+                random_number(rng, depth + 1, max_depth),
+            )
+        }
+    }
+
+    /// The largest number of pairs any pair is nested inside
+    fn max_pair_depth(num: &SnailfishNumber) -> Option<usize> {
+        match num {
+            SnailfishNumber::Nested(l, r) => Some(
+                max_pair_depth(l)
+                    .max(max_pair_depth(r))
+                    .map_or(0, |d| d + 1),
+            ),
+            SnailfishNumber::Literal(_) => None,
+        }
+    }
+
+    fn max_literal(num: &SnailfishNumber) -> usize {
+        match num {
+            SnailfishNumber::Nested(l, r) => max_literal(l).max(max_literal(r)),
+            SnailfishNumber::Literal(n) => *n,
+        }
+    }
+
+    fn assert_reduced(num: &SnailfishNumber, original: &SnailfishNumber) {
+        assert!(
+            max_pair_depth(num) < Some(4),
+            "{} reduced to {}, which is nested too deep",
+            original,
+            num
+        );
+        assert!(
+            max_literal(num) < 10,
+            "{} reduced to {}, which has a literal that should be split",
+            original,
+            num
+        );
+    }
+
+    #[test]
+    fn test_reduce_random() {
+        let mut rng = Rng(0x2021_1218);
+        let (mut num_explodes, mut num_splits) = (0, 0);
+        for _ in 0..1000 {
+            // Numbers in the puzzle never have pairs nested inside more than four pairs
+            let num = random_number(&mut rng, 0, 4);
+            assert!(max_pair_depth(&num) <= Some(4));
+            num_explodes += (max_pair_depth(&num) == Some(4)) as usize;
+            num_splits += (max_literal(&num) >= 10) as usize;
+            let reduced = num.reduce();
+            assert_reduced(&reduced, &num);
+            assert_eq!(reduced.reduce(), reduced);
+
+            // Adding two reduced numbers is how deep nesting happens while solving the puzzle
+            let other = random_number(&mut rng, 0, 4).reduce();
+            let sum = reduced.add(&other);
+            assert_reduced(&sum.reduce(), &sum);
+        }
+
+        // Make sure the generated numbers actually need both explodes and splits
+        assert!(num_explodes > 100);
+        assert!(num_splits > 100);
+    }
 }
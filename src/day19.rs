@@ -1,22 +1,23 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use nom::bytes::complete::tag;
 use nom::character::complete::one_of;
 use nom::combinator::{map, map_res, opt, recognize};
 use nom::multi::{many1, separated_list1};
 use nom::sequence::{delimited, pair, preceded, tuple};
 use nom::IResult;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::path::Path;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
-struct Coordinate {
-    x: isize,
-    y: isize,
-    z: isize,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coordinate {
+    pub x: isize,
+    pub y: isize,
+    pub z: isize,
 }
 
 impl Coordinate {
-    fn new(x: isize, y: isize, z: isize) -> Self {
+    pub fn new(x: isize, y: isize, z: isize) -> Self {
         Self { x, y, z }
     }
 
@@ -28,72 +29,202 @@ impl Coordinate {
         let c = self.sub(other);
         (c.x.abs() + c.y.abs() + c.z.abs()) as usize
     }
+
+    fn squared_distance(self, other: Self) -> usize {
+        let c = self.sub(other);
+        (c.x * c.x + c.y * c.y + c.z * c.z) as usize
+    }
 }
 
 #[derive(Debug)]
-struct DetectionCube {
+pub struct DetectionCube {
     scanners: HashSet<Coordinate>,
     beacons: HashSet<Coordinate>,
+    observations: HashMap<Coordinate, Vec<usize>>,
 }
 
 impl DetectionCube {
-    fn new(beacons: HashSet<Coordinate>) -> Self {
+    fn new(scanner_index: usize, beacons: HashSet<Coordinate>) -> Self {
         let mut scanners = HashSet::new();
         scanners.insert(Coordinate::new(0, 0, 0));
-        Self { scanners, beacons }
+        let observations = beacons.iter().map(|&b| (b, vec![scanner_index])).collect();
+        Self {
+            scanners,
+            beacons,
+            observations,
+        }
+    }
+
+    /// The indices of the scanners that saw each beacon, in ascending order. Scanners are indexed
+    /// by their position in the input
+    pub fn beacon_observations(&self) -> HashMap<Coordinate, Vec<usize>> {
+        self.observations.clone()
     }
 
-    fn from_cubes(mut detection_cubes: Vec<Self>) -> Self {
-        // Select one detection cube to start with and try to merge it with the rest
-        let mut detection_cube = detection_cubes.pop().unwrap();
+    /// Return the number of beacons and the largest manhattan distance between any two scanners
+    pub fn summary(&self) -> (usize, usize) {
+        let scanners = self.scanners.iter().copied().collect::<Vec<_>>();
+        let max_distance = scanners
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &s1)| {
+                scanners[i + 1..]
+                    .iter()
+                    .map(move |&s2| s1.manhattan_distance(s2))
+            })
+            .max()
+            .unwrap_or(0);
+        (self.beacons.len(), max_distance)
+    }
+
+    /// Merge all detection cubes into one. Gives up with an error after `max_iterations` merge
+    /// attempts, or never if it's `None`. It's also an error if some scanners can't be placed.
+    pub fn from_cubes(detection_cubes: Vec<Self>, max_iterations: Option<usize>) -> Result<Self> {
+        Self::merge_cubes(detection_cubes, max_iterations)?
+            .map_err(|unplaced| anyhow!("Unable to place scanners {:?}", unplaced))
+    }
+
+    /// Merge all detection cubes into one. If some scanners don't share enough beacons with the
+    /// others to be placed, their indices are returned instead. These are the same indices as
+    /// `beacon_observations` uses. Merging no cubes at all gives an empty detection cube without
+    /// any scanners or beacons.
+    pub fn try_from_cubes(detection_cubes: Vec<Self>) -> Result<Self, Vec<usize>> {
+        if detection_cubes.is_empty() {
+            return Ok(Self {
+                scanners: HashSet::new(),
+                beacons: HashSet::new(),
+                observations: HashMap::new(),
+            });
+        }
 
-        // NOTE: This will loop infinitely if there are scanners that don't share any beacons
-        let mut unmerged_detection_cubes = detection_cubes.into_iter().collect::<VecDeque<_>>();
-        while let Some(other_scanner) = unmerged_detection_cubes.pop_front() {
-            if let Some(m) = detection_cube.try_merge(&other_scanner) {
+        // Without an iteration limit and with at least one scanner, merging can't fail
+        Self::merge_cubes(detection_cubes, None).expect("There is at least one scanner")
+    }
+
+    fn merge_cubes(
+        detection_cubes: Vec<Self>,
+        max_iterations: Option<usize>,
+    ) -> Result<Result<Self, Vec<usize>>> {
+        // Start with the first scanner, since it decides the coordinate system, and try to merge
+        // it with the rest
+        let mut detection_cubes = detection_cubes.into_iter().enumerate();
+        let (_, mut detection_cube) = detection_cubes
+            .next()
+            .ok_or_else(|| anyhow!("There must be at least one scanner"))?;
+
+        let mut fingerprint = detection_cube.fingerprint();
+
+        let mut unmerged_detection_cubes = detection_cubes
+            .map(|(i, d)| {
+                let f = d.fingerprint();
+                (i, d, f)
+            })
+            .collect::<VecDeque<_>>();
+        let mut num_iterations = 0;
+        let mut failures_since_merge = 0;
+        while let Some((i, other_scanner, other_fingerprint)) = unmerged_detection_cubes.pop_front()
+        {
+            if max_iterations.is_some_and(|m| num_iterations >= m) {
+                return Err(anyhow!("Merging exceeded {} iterations", num_iterations));
+            }
+            num_iterations += 1;
+
+            // 12 shared beacons means at least 66 shared distances between them, so there is no
+            // point in trying all rotations and translations if there are fewer than that
+            let merged = if shared_distances(&fingerprint, &other_fingerprint) >= 66 {
+                detection_cube.try_merge(&other_scanner)
+            } else {
+                None
+            };
+
+            if let Some(m) = merged {
                 detection_cube = m;
+                fingerprint = detection_cube.fingerprint();
+                failures_since_merge = 0;
             } else {
-                unmerged_detection_cubes.push_back(other_scanner);
+                unmerged_detection_cubes.push_back((i, other_scanner, other_fingerprint));
+                failures_since_merge += 1;
+
+                // Every remaining scanner has failed to merge since the merged cube last changed,
+                // so trying again would fail in the same way
+                if failures_since_merge == unmerged_detection_cubes.len() {
+                    let mut unplaced = unmerged_detection_cubes
+                        .into_iter()
+                        .map(|(i, _, _)| i)
+                        .collect::<Vec<_>>();
+                    unplaced.sort_unstable();
+                    return Ok(Err(unplaced));
+                }
             }
         }
-        detection_cube
+        Ok(Ok(detection_cube))
     }
 
-    fn rotations(&self) -> Vec<Self> {
-        rotations(self.scanners.iter().copied())
-            .into_iter()
-            .zip(rotations(self.beacons.iter().copied()).into_iter())
-            .map(|(scanners, beacons)| Self { scanners, beacons })
-            .collect()
+    /// The pairwise squared distances between all beacons. These are the same no matter how the
+    /// scanner is rotated or translated.
+    ///
+    /// Several pairs of beacons may be equally far apart, so the distances are returned as a
+    /// multiset that maps each squared distance to the number of pairs that are that far apart.
+    pub fn fingerprint(&self) -> BTreeMap<usize, usize> {
+        let mut fingerprint = BTreeMap::new();
+        for &a in &self.beacons {
+            for &b in self.beacons.iter().filter(|&&b| a < b) {
+                *fingerprint.entry(a.squared_distance(b)).or_default() += 1;
+            }
+        }
+        fingerprint
     }
 
-    /// Move the origin to `origin`
-    fn translate(&self, origin: Coordinate) -> Self {
+    /// Move the origin to `origin` after applying the given rotation
+    fn transform(&self, rotation: usize, origin: Coordinate) -> Self {
+        let place = |c: &Coordinate| rotate(*c, rotation).sub(origin);
         Self {
-            scanners: self.scanners.iter().map(|c| c.sub(origin)).collect(),
-            beacons: self.beacons.iter().map(|c| c.sub(origin)).collect(),
+            scanners: self.scanners.iter().map(place).collect(),
+            beacons: self.beacons.iter().map(place).collect(),
+            observations: self
+                .observations
+                .iter()
+                .map(|(c, ids)| (place(c), ids.clone()))
+                .collect(),
         }
     }
 
-    fn translations(&'_ self) -> impl Iterator<Item = Self> + '_ {
-        self.beacons
-            .iter()
-            .copied()
-            .map(|new_origin| self.translate(new_origin))
+    fn union(mut self, other: Self) -> Self {
+        self.scanners.extend(other.scanners);
+        self.beacons.extend(other.beacons);
+        for (beacon, ids) in other.observations {
+            let observed_by = self.observations.entry(beacon).or_default();
+            observed_by.extend(ids);
+            observed_by.sort_unstable();
+            observed_by.dedup();
+        }
+        self
     }
 
     fn try_merge(&self, other: &Self) -> Option<Self> {
+        let other_rotations = rotations(other.beacons.iter().copied());
+
         // Translate this scanner's origin to all points within the scanner
-        for s in self.translations() {
+        for &origin in &self.beacons {
+            let beacons = self
+                .beacons
+                .iter()
+                .map(|c| c.sub(origin))
+                .collect::<HashSet<_>>();
+
             // We need to check all orientations for the given
-            for rotated_other in other.rotations() {
+            for (rotation, rotated_beacons) in other_rotations.iter().enumerate() {
                 // For every new origin we need to check that against the other scanner
-                for o in rotated_other.translations() {
-                    if o.beacons.intersection(&s.beacons).count() >= 12 {
-                        return Some(Self {
-                            scanners: o.scanners.union(&s.scanners).copied().collect(),
-                            beacons: o.beacons.union(&s.beacons).copied().collect(),
-                        });
+                for &other_origin in rotated_beacons {
+                    let num_shared = rotated_beacons
+                        .iter()
+                        .filter(|c| beacons.contains(&c.sub(other_origin)))
+                        .count();
+                    if num_shared >= 12 {
+                        return Some(
+                            self.transform(0, origin)
+                                .union(other.transform(rotation, other_origin)),
+                        );
                     }
                 }
             }
@@ -102,79 +233,59 @@ impl DetectionCube {
     }
 }
 
-fn rotations<I: Iterator<Item = Coordinate> + Clone>(it: I) -> Vec<HashSet<Coordinate>> {
-    vec![
+/// Count the pairwise distances that occur in both fingerprints. Distances that occur several
+/// times count as many times as they occur in both
+fn shared_distances(a: &BTreeMap<usize, usize>, b: &BTreeMap<usize, usize>) -> usize {
+    a.iter()
+        .filter_map(|(distance, &count)| Some(count.min(*b.get(distance)?)))
+        .sum()
+}
+
+/// The number of ways a scanner can be oriented
+const NUM_ROTATIONS: usize = 24;
+
+/// Apply one of the 24 rotations to a single coordinate
+fn rotate(c: Coordinate, rotation: usize) -> Coordinate {
+    match rotation {
         // All four rotations when original X faces X
-        it.clone().collect(),
-        it.clone()
-            .map(|c| Coordinate::new(c.x, -c.y, -c.z))
-            .collect(),
-        it.clone()
-            .map(|c| Coordinate::new(c.x, -c.z, c.y))
-            .collect(),
-        it.clone()
-            .map(|c| Coordinate::new(c.x, c.z, -c.y))
-            .collect(),
+        0 => c,
+        1 => Coordinate::new(c.x, -c.y, -c.z),
+        2 => Coordinate::new(c.x, -c.z, c.y),
+        3 => Coordinate::new(c.x, c.z, -c.y),
         // All four rotations when original X faces Y
-        it.clone()
-            .map(|c| Coordinate::new(-c.y, c.x, c.z))
-            .collect(),
-        it.clone()
-            .map(|c| Coordinate::new(-c.z, c.x, -c.y))
-            .collect(),
-        it.clone()
-            .map(|c| Coordinate::new(c.y, c.x, -c.z))
-            .collect(),
-        it.clone().map(|c| Coordinate::new(c.z, c.x, c.y)).collect(),
+        4 => Coordinate::new(-c.y, c.x, c.z),
+        5 => Coordinate::new(-c.z, c.x, -c.y),
+        6 => Coordinate::new(c.y, c.x, -c.z),
+        7 => Coordinate::new(c.z, c.x, c.y),
         // All four rotations when original X faces Z
-        it.clone()
-            .map(|c| Coordinate::new(-c.y, -c.z, c.x))
-            .collect(),
-        it.clone().map(|c| Coordinate::new(c.y, c.z, c.x)).collect(),
-        it.clone()
-            .map(|c| Coordinate::new(c.z, -c.y, c.x))
-            .collect(),
-        it.clone()
-            .map(|c| Coordinate::new(-c.z, c.y, c.x))
-            .collect(),
+        8 => Coordinate::new(-c.y, -c.z, c.x),
+        9 => Coordinate::new(c.y, c.z, c.x),
+        10 => Coordinate::new(c.z, -c.y, c.x),
+        11 => Coordinate::new(-c.z, c.y, c.x),
         // All four rotations when original X faces -X
-        it.clone()
-            .map(|c| Coordinate::new(-c.x, -c.y, c.z))
-            .collect(),
-        it.clone()
-            .map(|c| Coordinate::new(-c.x, -c.z, -c.y))
-            .collect(),
-        it.clone()
-            .map(|c| Coordinate::new(-c.x, c.y, -c.z))
-            .collect(),
-        it.clone()
-            .map(|c| Coordinate::new(-c.x, c.z, c.y))
-            .collect(),
+        12 => Coordinate::new(-c.x, -c.y, c.z),
+        13 => Coordinate::new(-c.x, -c.z, -c.y),
+        14 => Coordinate::new(-c.x, c.y, -c.z),
+        15 => Coordinate::new(-c.x, c.z, c.y),
         // All four rotations when original X faces -Y
-        it.clone()
-            .map(|c| Coordinate::new(c.y, -c.x, c.z))
-            .collect(),
-        it.clone()
-            .map(|c| Coordinate::new(-c.z, -c.x, c.y))
-            .collect(),
-        it.clone()
-            .map(|c| Coordinate::new(-c.y, -c.x, -c.z))
-            .collect(),
-        it.clone()
-            .map(|c| Coordinate::new(c.z, -c.x, -c.y))
-            .collect(),
+        16 => Coordinate::new(c.y, -c.x, c.z),
+        17 => Coordinate::new(-c.z, -c.x, c.y),
+        18 => Coordinate::new(-c.y, -c.x, -c.z),
+        19 => Coordinate::new(c.z, -c.x, -c.y),
         // All four rotations when original X faces -Z
-        it.clone()
-            .map(|c| Coordinate::new(c.y, -c.z, -c.x))
-            .collect(),
-        it.clone()
-            .map(|c| Coordinate::new(c.z, c.y, -c.x))
-            .collect(),
-        it.clone()
-            .map(|c| Coordinate::new(-c.y, c.z, -c.x))
-            .collect(),
-        it.map(|c| Coordinate::new(-c.z, -c.y, -c.x)).collect(),
-    ]
+        20 => Coordinate::new(c.y, -c.z, -c.x),
+        21 => Coordinate::new(c.z, c.y, -c.x),
+        22 => Coordinate::new(-c.y, c.z, -c.x),
+        23 => Coordinate::new(-c.z, -c.y, -c.x),
+        _ => panic!("There are only {} rotations", NUM_ROTATIONS),
+    }
+}
+
+/// Return the coordinates in every one of the 24 rotations, in the same order as `rotate`
+fn rotations<I: Iterator<Item = Coordinate> + Clone>(it: I) -> Vec<HashSet<Coordinate>> {
+    (0..NUM_ROTATIONS)
+        .map(|rotation| it.clone().map(|c| rotate(c, rotation)).collect())
+        .collect()
 }
 
 fn parse_number(input: &str) -> IResult<&str, isize> {
@@ -184,7 +295,16 @@ fn parse_number(input: &str) -> IResult<&str, isize> {
     )(input)
 }
 
-fn parse_scanners(input: &str) -> Result<Vec<DetectionCube>, nom::Err<nom::error::Error<String>>> {
+/// Parse all scanner reports. Windows line endings are accepted as well.
+pub fn parse_scanners(
+    input: &str,
+) -> Result<Vec<DetectionCube>, nom::Err<nom::error::Error<String>>> {
+    parse_scanners_lf(&input.replace("\r\n", "\n"))
+}
+
+fn parse_scanners_lf(
+    input: &str,
+) -> Result<Vec<DetectionCube>, nom::Err<nom::error::Error<String>>> {
     separated_list1(
         tag("\n\n"),
         map(
@@ -202,45 +322,36 @@ fn parse_scanners(input: &str) -> Result<Vec<DetectionCube>, nom::Err<nom::error
                     ),
                 ),
             ),
-            |(_, beacons)| DetectionCube::new(beacons.into_iter().collect()),
+            |(_, beacons)| beacons,
         ),
     )(input)
-    .map(|(_, scanners)| scanners)
+    .map(|(_, scanners)| {
+        // Scanners are identified by their position in the input, no matter what their headers
+        // say, so they are named the same way as in `DetectionCube::try_from_cubes`
+        scanners
+            .into_iter()
+            .enumerate()
+            .map(|(i, beacons)| DetectionCube::new(i, beacons.into_iter().collect()))
+            .collect()
+    })
     .map_err(|e: nom::Err<nom::error::Error<&str>>| e.to_owned())
 }
 
-fn part_a(detection_cube: &DetectionCube) -> usize {
-    detection_cube.beacons.len()
-}
-
-fn part_b(detection_cube: &DetectionCube) -> Option<usize> {
-    detection_cube
-        .scanners
-        .iter()
-        .copied()
-        .flat_map(|s1| {
-            detection_cube
-                .scanners
-                .iter()
-                .copied()
-                .filter(move |s2| s1 != *s2)
-                .map(move |s2| s1.manhattan_distance(s2))
-        })
-        .max()
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let detection_cube = DetectionCube::from_cubes(parse_scanners(input)?, None)?;
+    let (num_beacons, max_distance) = detection_cube.summary();
+    Ok((num_beacons, Some(max_distance)))
 }
 
 pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let input = std::fs::read_to_string(path)?;
-    let detection_cube = DetectionCube::from_cubes(parse_scanners(&input)?);
-    Ok((part_a(&detection_cube), part_b(&detection_cube)))
+    solve(&std::fs::read_to_string(path)?)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_parsing() -> Result<()> {
+    fn example() -> String {
         let mut example = String::new();
         example.push_str("--- scanner 0 ---\n");
         example.push_str("404,-588,-901\n");
@@ -268,7 +379,7 @@ mod tests {
         example.push_str("443,580,662\n");
         example.push_str("-789,900,-551\n");
         example.push_str("459,-707,401\n");
-        example.push_str("\n");
+        example.push('\n');
         example.push_str("--- scanner 1 ---\n");
         example.push_str("686,422,578\n");
         example.push_str("605,423,415\n");
@@ -295,7 +406,7 @@ mod tests {
         example.push_str("807,-499,-711\n");
         example.push_str("755,-354,-619\n");
         example.push_str("553,889,-390\n");
-        example.push_str("\n");
+        example.push('\n');
         example.push_str("--- scanner 2 ---\n");
         example.push_str("649,640,665\n");
         example.push_str("682,-795,504\n");
@@ -323,7 +434,7 @@ mod tests {
         example.push_str("673,-379,-804\n");
         example.push_str("-742,-814,-386\n");
         example.push_str("577,-820,562\n");
-        example.push_str("\n");
+        example.push('\n');
         example.push_str("--- scanner 3 ---\n");
         example.push_str("-589,542,597\n");
         example.push_str("605,-692,669\n");
@@ -350,7 +461,7 @@ mod tests {
         example.push_str("-868,-804,481\n");
         example.push_str("614,-800,639\n");
         example.push_str("595,780,-596\n");
-        example.push_str("\n");
+        example.push('\n');
         example.push_str("--- scanner 4 ---\n");
         example.push_str("727,592,562\n");
         example.push_str("-293,-554,779\n");
@@ -378,11 +489,178 @@ mod tests {
         example.push_str("891,-625,532\n");
         example.push_str("-652,-548,-490\n");
         example.push_str("30,-46,-14\n");
+        example
+    }
+
+    #[test]
+    fn test_parsing() -> Result<()> {
+        let example = example();
+        let detection_cube = DetectionCube::from_cubes(parse_scanners(&example)?, None)?;
+        assert_eq!(detection_cube.summary(), (79, 3621));
+
+        // Four scanners must be merged into the first, so two iterations can't be enough
+        assert!(DetectionCube::from_cubes(parse_scanners(&example)?, Some(2)).is_err());
+        assert!(DetectionCube::from_cubes(parse_scanners(&example)?, Some(100)).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_crlf() -> Result<()> {
+        let example = example();
+        let two_scanners = example
+            .split("\n\n")
+            .take(2)
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let two_scanners_crlf = two_scanners.replace('\n', "\r\n");
+        assert!(two_scanners_crlf.contains(" ---\r\n"));
+
+        let lf = parse_scanners(&two_scanners)?;
+        let crlf = parse_scanners(&two_scanners_crlf)?;
+        assert_eq!(lf.len(), 2);
+        assert_eq!(crlf.len(), 2);
+        for (a, b) in lf.iter().zip(&crlf) {
+            assert_eq!(a.beacons, b.beacons);
+            assert_eq!(a.beacon_observations(), b.beacon_observations());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary() -> Result<()> {
+        let scanners = parse_scanners(&example())?;
+        assert_eq!(scanners[0].summary(), (25, 0));
+        assert_eq!(
+            DetectionCube::from_cubes(scanners, None)?.summary(),
+            (79, 3621)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fingerprint() -> Result<()> {
+        let scanners = parse_scanners(&example())?;
+        let fingerprints = scanners
+            .iter()
+            .map(DetectionCube::fingerprint)
+            .collect::<Vec<_>>();
+        let shared = |a: usize, b: usize| shared_distances(&fingerprints[a], &fingerprints[b]);
+
+        // Scanner 0 and 1 share 12 beacons while 0 and 2 don't overlap at all
+        assert!(shared(0, 1) >= 66);
+        assert!(shared(0, 2) < 66);
+        Ok(())
+    }
+
+    #[test]
+    fn test_repeated_distances() -> Result<()> {
+        // Twelve beacons on a regular grid have many pairs of beacons that are equally far apart
+        let grid = (0..3)
+            .flat_map(|x| (0..2).flat_map(move |y| (0..2).map(move |z| (x, y, z))))
+            .map(|(x, y, z)| Coordinate::new(100 * x + 7, 100 * y - 13, 100 * z + 29))
+            .collect::<Vec<_>>();
+        assert_eq!(grid.len(), 12);
+
+        // The second scanner is rotated and placed somewhere else, and sees one other beacon
+        let format = |beacons: &[Coordinate]| {
+            beacons
+                .iter()
+                .map(|c| format!("{},{},{}", c.x, c.y, c.z))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let mut moved = grid
+            .iter()
+            .map(|c| Coordinate::new(-c.y - 500, c.x + 200, c.z + 50))
+            .collect::<Vec<_>>();
+        moved.push(Coordinate::new(-900, 900, -900));
+        let input = format!(
+            "--- scanner 0 ---\n{}\n\n--- scanner 1 ---\n{}",
+            format(&grid),
+            format(&moved)
+        );
+
+        let scanners = parse_scanners(&input)?;
+        let fingerprints = scanners
+            .iter()
+            .map(DetectionCube::fingerprint)
+            .collect::<Vec<_>>();
+
+        // There are fewer than 66 distinct distances, but the beacons still overlap
+        assert!(fingerprints[0].len() < 66);
+        assert_eq!(shared_distances(&fingerprints[0], &fingerprints[1]), 66);
+
+        let detection_cube = DetectionCube::try_from_cubes(scanners).unwrap();
+        assert_eq!(detection_cube.summary().0, 13);
+        Ok(())
+    }
+
+    #[test]
+    fn test_beacon_observations() -> Result<()> {
+        let detection_cube = DetectionCube::from_cubes(parse_scanners(&example())?, None)?;
+        let observations = detection_cube.beacon_observations();
+        assert_eq!(observations.len(), 79);
+        assert!(observations.values().all(|ids| !ids.is_empty()));
+
+        // Scanner 0 and 1 overlap on exactly 12 beacons
+        let seen_by = |a, b| {
+            observations
+                .values()
+                .filter(|ids| ids.contains(&a) && ids.contains(&b))
+                .count()
+        };
+        assert_eq!(seen_by(0, 1), 12);
+
+        // Scanners are identified by position rather than by the number in their header
+        let input = "--- scanner 7 ---\n1,2,3\n\n--- scanner 3 ---\n4,5,6";
+        let scanners = parse_scanners(input)?;
+        assert_eq!(
+            scanners[1].beacon_observations()[&Coordinate::new(4, 5, 6)],
+            vec![1]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rotate() {
+        let c = Coordinate::new(1, 2, 3);
+        let rotated = (0..NUM_ROTATIONS)
+            .map(|r| rotate(c, r))
+            .collect::<HashSet<_>>();
+        assert_eq!(rotated.len(), NUM_ROTATIONS);
+        assert_eq!(rotate(c, 0), c);
+        for (r, coordinates) in rotations([c].into_iter()).into_iter().enumerate() {
+            assert_eq!(coordinates, [rotate(c, r)].into_iter().collect());
+        }
+    }
+
+    #[test]
+    fn test_try_from_cubes() -> Result<()> {
+        let example = example();
+        let detection_cube = DetectionCube::try_from_cubes(parse_scanners(&example)?).unwrap();
+        assert_eq!(detection_cube.summary(), (79, 3621));
+
+        // Add a scanner in the middle that doesn't see any of the other beacons
+        let mut reports = example.split("\n\n").collect::<Vec<_>>();
+        reports.insert(2, "--- scanner 5 ---\n1,2,3\n4,5,6\n7,8,9");
+        let scanners = parse_scanners(&reports.join("\n\n"))?;
+        assert_eq!(scanners.len(), 6);
+        assert_eq!(
+            scanners[2].beacon_observations()[&Coordinate::new(1, 2, 3)],
+            vec![2]
+        );
+        assert_eq!(
+            DetectionCube::try_from_cubes(scanners).unwrap_err(),
+            vec![2]
+        );
 
-        let detection_cube = DetectionCube::from_cubes(parse_scanners(&example)?);
-        assert_eq!(part_a(&detection_cube), 79);
-        assert_eq!(part_b(&detection_cube), Some(3621));
+        let scanners = parse_scanners(&reports.join("\n\n"))?;
+        assert!(DetectionCube::from_cubes(scanners, None).is_err());
 
+        let empty = DetectionCube::try_from_cubes(Vec::new()).unwrap();
+        assert_eq!(empty.summary(), (0, 0));
+        assert!(DetectionCube::from_cubes(Vec::new(), None).is_err());
         Ok(())
     }
 }
@@ -1,32 +1,44 @@
-use anyhow::{anyhow, Error, Result};
-use std::fs::File;
-use std::io::{self, BufRead};
+use anyhow::Result;
+use std::num::ParseIntError;
 use std::path::Path;
 use std::str::FromStr;
+use thiserror::Error;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     Forward(isize),
     Up(isize),
     Down(isize),
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseDirectionError {
+    #[error("No value found")]
+    MissingValue,
+    #[error("Invalid value: {0}")]
+    InvalidValue(#[from] ParseIntError),
+    #[error("Unknown direction {0}")]
+    UnknownDirection(String),
+}
+
 impl FromStr for Direction {
-    type Err = Error;
+    type Err = ParseDirectionError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split(' ');
+        let mut parts = s.split_whitespace();
 
-        let direction = parts.next().unwrap(); // Unwrap is fine since it can't fail here
+        let direction = parts.next().unwrap_or_default();
         let value: isize = parts
             .next()
-            .ok_or_else(|| anyhow!("No value found"))?
+            .ok_or(ParseDirectionError::MissingValue)?
             .parse()?;
 
         Ok(match direction {
             "forward" => Self::Forward(value),
             "up" => Self::Up(value),
             "down" => Self::Down(value),
-            d => return Err(anyhow!("Unknown direction {}", d)),
+            d => return Err(ParseDirectionError::UnknownDirection(d.to_owned())),
         })
     }
 }
@@ -63,20 +75,23 @@ pub fn part_b(directions: &[Direction]) -> isize {
     hpos * depth
 }
 
-pub fn main(path: &Path) -> Result<(isize, Option<isize>)> {
-    let file = File::open(path)?;
-    let directions = io::BufReader::new(file)
+pub fn solve(input: &str) -> Result<(isize, Option<isize>)> {
+    let directions = input
         .lines()
-        .map(|lr| lr?.parse::<Direction>())
-        .collect::<Result<Vec<Direction>>>()?;
+        .map(|l| l.parse::<Direction>())
+        .collect::<Result<Vec<Direction>, _>>()?;
     Ok((part_a(&directions), Some(part_b(&directions))))
 }
 
+pub fn main(path: &Path) -> Result<(isize, Option<isize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const DIRECTIONS: &'static [Direction] = &[
+    const DIRECTIONS: &[Direction] = &[
         Direction::Forward(5),
         Direction::Down(5),
         Direction::Forward(8),
@@ -87,13 +102,46 @@ mod tests {
 
     #[test]
     fn test_part_a() -> Result<()> {
-        assert_eq!(part_a(&DIRECTIONS), 150);
+        assert_eq!(part_a(DIRECTIONS), 150);
         Ok(())
     }
 
     #[test]
     fn test_part_b() -> Result<()> {
-        assert_eq!(part_b(&DIRECTIONS), 900);
+        assert_eq!(part_b(DIRECTIONS), 900);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert_eq!("up 3".parse::<Direction>(), Ok(Direction::Up(3)));
+        assert!(matches!(
+            "sideways 3".parse::<Direction>(),
+            Err(ParseDirectionError::UnknownDirection(d)) if d == "sideways"
+        ));
+        assert_eq!(
+            "down".parse::<Direction>(),
+            Err(ParseDirectionError::MissingValue)
+        );
+        assert!(matches!(
+            "forward x".parse::<Direction>(),
+            Err(ParseDirectionError::InvalidValue(_))
+        ));
+        assert!(solve("forward 1\nsideways 3\n").is_err());
+        assert_eq!(
+            "".parse::<Direction>(),
+            Err(ParseDirectionError::MissingValue)
+        );
+    }
+
+    #[test]
+    fn test_parse_tabs() -> Result<()> {
+        assert_eq!("down\t8".parse::<Direction>(), Ok(Direction::Down(8)));
+        assert_eq!("forward\t5".parse::<Direction>(), Ok(Direction::Forward(5)));
+        assert_eq!(
+            solve("forward\t5\ndown 5\nforward 8\nup\t3\ndown 8\nforward 2\n")?,
+            (150, Some(900))
+        );
         Ok(())
     }
 }
@@ -2,17 +2,22 @@ use anyhow::{anyhow, Result};
 use std::collections::HashSet;
 use std::path::Path;
 
-struct SparseImage {
+pub type Coordinate = (isize, isize);
+
+/// An infinite image where only the pixels within the bounds are tracked individually. Every pixel
+/// outside of the bounds has the same state.
+#[derive(Debug, Clone)]
+pub struct SparseImage {
     min_x: isize,
     max_x: isize,
     min_y: isize,
     max_y: isize,
     rest_is_light: bool,
-    light_pixels: HashSet<(isize, isize)>,
+    light_pixels: HashSet<Coordinate>,
 }
 
 impl SparseImage {
-    fn new(light_pixels: HashSet<(isize, isize)>) -> Self {
+    pub fn new(light_pixels: HashSet<Coordinate>) -> Self {
         Self {
             min_x: light_pixels.iter().map(|(x, _)| *x).min().unwrap_or(0),
             max_x: light_pixels.iter().map(|(x, _)| *x).max().unwrap_or(0),
@@ -23,7 +28,34 @@ impl SparseImage {
         }
     }
 
-    fn is_light(&self, (x, y): (isize, isize)) -> bool {
+    /// Iterate over all lit pixels within the bounds of the image
+    pub fn lit_pixels(&self) -> impl Iterator<Item = Coordinate> + '_ {
+        self.light_pixels.iter().copied()
+    }
+
+    /// The top left and bottom right corner of the tracked part of the image
+    pub fn bounds(&self) -> (Coordinate, Coordinate) {
+        ((self.min_x, self.min_y), (self.max_x, self.max_y))
+    }
+
+    /// The number of lit pixels, or `None` if there is an infinite number of them
+    pub fn light_count(&self) -> Option<usize> {
+        if self.rest_is_light {
+            return None;
+        }
+        Some(self.light_pixels.len())
+    }
+
+    /// The number of lit pixels within the rectangle from `min` to `max`, inclusive. Pixels
+    /// outside of the tracked part of the image count if the rest of the image is lit.
+    pub fn light_count_in(&self, (min_x, min_y): Coordinate, (max_x, max_y): Coordinate) -> usize {
+        (min_y..=max_y)
+            .flat_map(|y| (min_x..=max_x).map(move |x| (x, y)))
+            .filter(|&c| self.is_light(c))
+            .count()
+    }
+
+    fn is_light(&self, (x, y): Coordinate) -> bool {
         if (self.min_x..=self.max_x).contains(&x) && (self.min_y..=self.max_y).contains(&y) {
             self.light_pixels.contains(&(x, y))
         } else {
@@ -31,7 +63,7 @@ impl SparseImage {
         }
     }
 
-    fn enhance(&mut self, image_enhancement_algorithm: &[bool; 512]) {
+    pub fn enhance(&mut self, image_enhancement_algorithm: &[bool; 512]) {
         let mut light_pixels = HashSet::new();
 
         // We search an area just outside the image as well since the pixels inside the current
@@ -72,10 +104,38 @@ impl SparseImage {
         self.min_y -= 1;
         self.max_y += 1;
     }
+
+    /// The number of lit pixels after the given number of enhancements, or `None` if there is an
+    /// infinite number of them. This happens after an odd number of enhancements when the
+    /// algorithm lights up every dark pixel with only dark neighbors, making the background flash.
+    pub fn light_count_after(
+        &self,
+        image_enhancement_algorithm: &[bool; 512],
+        steps: usize,
+    ) -> Option<usize> {
+        match steps {
+            0 => self.light_count(),
+            n => self
+                .clone()
+                .enhancements(image_enhancement_algorithm)
+                .nth(n - 1)?
+                .light_count(),
+        }
+    }
+
+    /// Iterate over the image after each successive enhancement, starting with the first one
+    pub fn enhancements(
+        mut self,
+        image_enhancement_algorithm: &[bool; 512],
+    ) -> impl Iterator<Item = Self> + '_ {
+        std::iter::from_fn(move || {
+            self.enhance(image_enhancement_algorithm);
+            Some(self.clone())
+        })
+    }
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let input = std::fs::read_to_string(path)?;
+fn parse_input(input: &str) -> Result<([bool; 512], SparseImage)> {
     let (enhancement_str, image_str) = input
         .split_once("\n\n")
         .ok_or_else(|| anyhow!("Invalid input"))?;
@@ -108,16 +168,140 @@ pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
         })
         .collect::<HashSet<_>>();
 
-    let mut image = SparseImage::new(light_pixels);
-    for _ in 0..2 {
+    Ok((image_enhancement_algorithm, SparseImage::new(light_pixels)))
+}
+
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let (image_enhancement_algorithm, image) = parse_input(input)?;
+    let mut enhancements = image.enhancements(&image_enhancement_algorithm);
+
+    let a = enhancements
+        .nth(1)
+        .and_then(|image| image.light_count())
+        .ok_or_else(|| anyhow!("Infinitely many pixels are lit after 2 enhancements"))?;
+
+    // The iterator has already yielded the first 2 enhancements
+    let b = enhancements
+        .nth(47)
+        .and_then(|image| image.light_count())
+        .ok_or_else(|| anyhow!("Infinitely many pixels are lit after 50 enhancements"))?;
+
+    Ok((a, Some(b)))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> String {
+        let mut example = String::new();
+        example
+            .push_str("..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..##");
+        example
+            .push_str("#..######.###...####..#..#####..##..#.#####...##.#.#..#.##..#.#......#.###");
+        example
+            .push_str(".######.###.####...#.##.##..#..#..#####.....#.#....###..#.##......#.....#.");
+        example
+            .push_str(".#..#..##..#...##.######.####.####.#.#...#.......#..#.#.#...####.##.#.....");
+        example
+            .push_str(".#..#...##.#.##..#...##.#.##..###.#......#.#.......#.#.#.####.###.##...#..");
+        example.push_str(
+            "...####.#..#..#.##.#....##..#.####....##...##..#...#......#.#.......#......",
+        );
+        example.push_str(".##..####..#...#.#.#...##..#.#..###..#####........#..####......#..#\n");
+        example.push('\n');
+        example.push_str("#..#.\n");
+        example.push_str("#....\n");
+        example.push_str("##..#\n");
+        example.push_str("..#..\n");
+        example.push_str("..###\n");
+        example
+    }
+
+    #[test]
+    fn test_example() -> Result<()> {
+        assert_eq!(solve(&example())?, (35, Some(3351)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_accessors() -> Result<()> {
+        let (image_enhancement_algorithm, mut image) = parse_input(&example())?;
+        assert_eq!(image.bounds(), ((0, 0), (4, 4)));
+        assert_eq!(image.light_count(), Some(10));
+
         image.enhance(&image_enhancement_algorithm);
+        image.enhance(&image_enhancement_algorithm);
+        assert_eq!(image.bounds(), ((-2, -2), (6, 6)));
+        assert_eq!(image.light_count(), Some(35));
+
+        let ((min_x, min_y), (max_x, max_y)) = image.bounds();
+        let mut lit_pixels = image.lit_pixels().collect::<Vec<_>>();
+        lit_pixels.sort_unstable();
+        assert_eq!(lit_pixels.len(), 35);
+        assert!(lit_pixels
+            .iter()
+            .all(|&(x, y)| (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y)));
+        assert_eq!(lit_pixels.first(), Some(&(-2, 0)));
+        Ok(())
     }
-    let a = image.light_pixels.len();
 
-    for _ in 2..50 {
+    #[test]
+    fn test_light_count_in() -> Result<()> {
+        let (image_enhancement_algorithm, mut image) = parse_input(&example())?;
+        assert_eq!(image.light_count_in((0, 0), (1, 1)), 2);
+        assert_eq!(image.light_count_in((-1, -1), (0, 0)), 1);
+        assert_eq!(image.light_count_in((1, 1), (0, 0)), 0);
+
+        image.rest_is_light = true;
+        assert_eq!(image.light_count_in((-1, -1), (0, 0)), 4);
+        assert_eq!(image.light_count_in((10, 10), (11, 12)), 6);
+
+        image.rest_is_light = false;
+        image.enhance(&image_enhancement_algorithm);
         image.enhance(&image_enhancement_algorithm);
+        let (min, max) = image.bounds();
+        assert_eq!(image.light_count_in(min, max), 35);
+        Ok(())
     }
-    let b = image.light_pixels.len();
 
-    Ok((a, Some(b)))
+    #[test]
+    fn test_enhancements() -> Result<()> {
+        let (image_enhancement_algorithm, image) = parse_input(&example())?;
+        let mut enhancements = image.enhancements(&image_enhancement_algorithm);
+        assert_eq!(enhancements.next().and_then(|i| i.light_count()), Some(24));
+        assert_eq!(enhancements.next().and_then(|i| i.light_count()), Some(35));
+
+        let image = parse_input(&example())?.1;
+        let second = image.enhancements(&image_enhancement_algorithm).nth(1);
+        assert_eq!(second.and_then(|i| i.light_count()), Some(35));
+        Ok(())
+    }
+
+    #[test]
+    fn test_light_count_after() -> Result<()> {
+        let (image_enhancement_algorithm, image) = parse_input(&example())?;
+        assert_eq!(
+            image.light_count_after(&image_enhancement_algorithm, 0),
+            Some(10)
+        );
+        assert_eq!(
+            image.light_count_after(&image_enhancement_algorithm, 2),
+            Some(35)
+        );
+
+        // Make the background flash by lighting pixels with only dark neighbors and turning off
+        // pixels with only lit neighbors
+        let mut flashing = image_enhancement_algorithm;
+        flashing[0] = true;
+        flashing[511] = false;
+        assert_eq!(image.light_count_after(&flashing, 1), None);
+        assert!(image.light_count_after(&flashing, 2).is_some());
+        assert_eq!(image.light_count_after(&flashing, 3), None);
+        Ok(())
+    }
 }
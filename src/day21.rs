@@ -1,13 +1,34 @@
 use anyhow::{anyhow, Result};
-use std::collections::HashMap;
 use std::path::Path;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 struct UniverseSplitter {
-    cache: HashMap<(usize, usize, usize, usize), (usize, usize)>,
+    /// Outcomes of previously computed scenarios, indexed by `cache_index`
+    cache: Vec<Option<(usize, usize)>>,
+    target_score: usize,
 }
 
 impl UniverseSplitter {
+    fn new(target_score: usize) -> Self {
+        let num_states = 10 * (target_score + 1);
+        Self {
+            cache: vec![None; num_states * num_states],
+            target_score,
+        }
+    }
+
+    /// Pack a scenario into an index into the cache. Positions are 1 to 10 and the remaining
+    /// scores can never be higher than the target score
+    fn cache_index(
+        &self,
+        (p1_pos, p1_rem_score, p2_pos, p2_rem_score): (usize, usize, usize, usize),
+    ) -> usize {
+        let num_scores = self.target_score + 1;
+        let p1 = (p1_pos - 1) * num_scores + p1_rem_score;
+        let p2 = (p2_pos - 1) * num_scores + p2_rem_score;
+        p1 * 10 * num_scores + p2
+    }
+
     /// Number of outcomes where player 1 and 2 wins respectively given the starting conditions
     fn num_wins(
         &mut self,
@@ -35,9 +56,10 @@ impl UniverseSplitter {
                 // have already computed this exact scenario before. Note that we swap the players
                 // in the argument list since it's the other player's turn now
                 let args = (p2_pos, p2_rem_score, p1_pos, p1_rem_score);
-                let (n_p2, n_p1) = self.cache.get(&args).copied().unwrap_or_else(|| {
+                let index = self.cache_index(args);
+                let (n_p2, n_p1) = self.cache[index].unwrap_or_else(|| {
                     let outcomes = self.num_wins(args.0, args.1, args.2, args.3);
-                    self.cache.insert(args, outcomes);
+                    self.cache[index] = Some(outcomes);
                     outcomes
                 });
 
@@ -49,37 +71,44 @@ impl UniverseSplitter {
     }
 }
 
-fn part_a(mut player1_pos: usize, mut player2_pos: usize) -> usize {
-    let mut is_player1s_turn = true;
-    let mut player1_score = 0;
-    let mut player2_score = 0;
-
-    let mut dice = 1..;
+/// Play the game with the deterministic dice and return every turn as the player (1 or 2), the sum
+/// of the three rolls, the player's new position and the player's new score
+pub fn part_a_log(player1_pos: usize, player2_pos: usize) -> Vec<(usize, usize, usize, usize)> {
+    let mut positions = [player1_pos, player2_pos];
+    let mut scores = [0, 0];
+    let mut player = 0;
 
-    while player1_score < 1000 && player2_score < 1000 {
-        let (pos, score) = if is_player1s_turn {
-            (&mut player1_pos, &mut player1_score)
-        } else {
-            (&mut player2_pos, &mut player2_score)
-        };
+    let mut dice = (1..=100).cycle();
 
+    let mut log = Vec::new();
+    while scores.iter().all(|&score| score < 1000) {
         let roll = dice.next().unwrap() + dice.next().unwrap() + dice.next().unwrap();
-        *pos = (*pos + roll - 1) % 10 + 1;
-        *score += *pos;
+        positions[player] = (positions[player] + roll - 1) % 10 + 1;
+        scores[player] += positions[player];
+        log.push((player + 1, roll, positions[player], scores[player]));
 
-        is_player1s_turn = !is_player1s_turn;
+        player = 1 - player;
     }
-    (dice.next().unwrap() - 1) * player1_score.min(player2_score)
+    log
 }
 
-fn part_b(player1_pos: usize, player2_pos: usize) -> usize {
-    let mut universe_splitter = UniverseSplitter::default();
+fn part_a(player1_pos: usize, player2_pos: usize) -> usize {
+    let log = part_a_log(player1_pos, player2_pos);
+
+    // The game ends on the winner's turn, so the turn before it has the loser's final score
+    let loser_score = log.iter().rev().nth(1).map_or(0, |&(_, _, _, score)| score);
+    3 * log.len() * loser_score
+}
+
+/// Return the number of universes the most successful player wins in when playing with the Dirac
+/// dice
+pub fn part_b(player1_pos: usize, player2_pos: usize) -> usize {
+    let mut universe_splitter = UniverseSplitter::new(21);
     let (p1_wins, p2_wins) = universe_splitter.num_wins(player1_pos, 21, player2_pos, 21);
     p1_wins.max(p2_wins)
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let input = std::fs::read_to_string(path)?;
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
     let (player1_str, player2_str) = input
         .split_once("\n")
         .ok_or_else(|| anyhow!("Invalid input"))?;
@@ -97,9 +126,17 @@ pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
         _ => Err(anyhow!("Invalid starting position for player 2")),
     }?;
 
+    if !(1..=10).contains(&player1) || !(1..=10).contains(&player2) {
+        return Err(anyhow!("Starting positions must be between 1 and 10"));
+    }
+
     Ok((part_a(player1, player2), Some(part_b(player1, player2))))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -109,8 +146,43 @@ mod tests {
         assert_eq!(part_a(4, 8), 739785);
     }
 
+    #[test]
+    fn test_part_a_log() {
+        let log = part_a_log(4, 8);
+        assert_eq!(log.len(), 331);
+        assert_eq!(&log[..2], [(1, 6, 10, 10), (2, 15, 3, 3)]);
+        assert_eq!(log[log.len() - 2], (2, 88 + 89 + 90, 3, 745));
+        assert_eq!(log.last(), Some(&(1, 91 + 92 + 93, 10, 1000)));
+    }
+
     #[test]
     fn test_part_b() {
         assert_eq!(part_b(4, 8), 444_356_092_776_315);
     }
+
+    #[test]
+    fn test_cache_index() {
+        // Every scenario must map to its own slot in the cache
+        let universe_splitter = UniverseSplitter::new(21);
+        let mut seen = vec![false; universe_splitter.cache.len()];
+        for p1_pos in 1..=10 {
+            for p1_rem_score in 0..=21 {
+                for p2_pos in 1..=10 {
+                    for p2_rem_score in 0..=21 {
+                        let args = (p1_pos, p1_rem_score, p2_pos, p2_rem_score);
+                        let index = universe_splitter.cache_index(args);
+                        assert!(!seen[index]);
+                        seen[index] = true;
+                    }
+                }
+            }
+        }
+        assert!(seen.into_iter().all(|s| s));
+    }
+
+    #[test]
+    fn test_invalid_position() {
+        let input = "Player 1 starting position: 11\nPlayer 2 starting position: 8\n";
+        assert!(solve(input).is_err());
+    }
 }
@@ -2,31 +2,51 @@ use anyhow::Result;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
 use nom::character::complete::one_of;
-use nom::combinator::{map, map_res, opt, recognize, value};
+use nom::combinator::{map, map_opt, map_res, opt, recognize, value};
 use nom::multi::many1;
 use nom::sequence::{pair, preceded, separated_pair, tuple};
 use nom::IResult;
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::ops::RangeInclusive;
 use std::path::Path;
 
 #[derive(Debug)]
-struct RebootStep {
-    turn_on: bool,
-    cube: CubeSelection,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RebootStep {
+    pub turn_on: bool,
+    pub cube: CubeSelection,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct CubeSelection {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedCubeSelection"))]
+pub struct CubeSelection {
     x: RangeInclusive<isize>,
     y: RangeInclusive<isize>,
     z: RangeInclusive<isize>,
 }
 
+/// A cube selection that may be empty, which is only used to deserialize through
+/// `CubeSelection::new`
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedCubeSelection {
+    x: RangeInclusive<isize>,
+    y: RangeInclusive<isize>,
+    z: RangeInclusive<isize>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedCubeSelection> for CubeSelection {
+    type Error = anyhow::Error;
+
+    fn try_from(c: UncheckedCubeSelection) -> Result<Self> {
+        Self::new(c.x, c.y, c.z).ok_or_else(|| anyhow::anyhow!("Cube selections can't be empty"))
+    }
+}
+
 impl CubeSelection {
-    fn new(
+    pub fn new(
         x: RangeInclusive<isize>,
         y: RangeInclusive<isize>,
         z: RangeInclusive<isize>,
@@ -39,7 +59,18 @@ impl CubeSelection {
     }
 
     fn len(&self) -> usize {
-        self.x.clone().count() * self.y.clone().count() * self.z.clone().count()
+        let side = |r: &RangeInclusive<isize>| r.end().abs_diff(*r.start()) + 1;
+        side(&self.x) * side(&self.y) * side(&self.z)
+    }
+
+    /// The number of cubes in the selection, which can't overflow no matter how large it is
+    fn volume(&self) -> u128 {
+        let side = |r: &RangeInclusive<isize>| r.end().abs_diff(*r.start()) as u128 + 1;
+        side(&self.x) * side(&self.y) * side(&self.z)
+    }
+
+    pub fn contains(&self, (x, y, z): (isize, isize, isize)) -> bool {
+        self.x.contains(&x) && self.y.contains(&y) && self.z.contains(&z)
     }
 
     fn bounding_box(&self, other: &Self) -> Self {
@@ -102,6 +133,34 @@ impl CubeSelection {
     }
 }
 
+/// Return the total volume covered by the given selections, counting overlapping parts only once
+pub fn volume_of_union(cubes: &[CubeSelection]) -> u128 {
+    let mut disjoint: Vec<CubeSelection> = Vec::new();
+    for cube in cubes {
+        disjoint = disjoint
+            .into_iter()
+            .flat_map(|c| c.difference(cube).into_iter())
+            .collect();
+        disjoint.push(cube.clone());
+    }
+    disjoint.iter().map(CubeSelection::volume).sum()
+}
+
+/// Return the region covered by every step that turns cubes on, if they all overlap
+pub fn common_region(reboot_steps: &[RebootStep]) -> Option<CubeSelection> {
+    let mut cubes = reboot_steps.iter().filter(|s| s.turn_on).map(|s| &s.cube);
+    let first = cubes.next()?.clone();
+    cubes.try_fold(first, |region, cube| region.intersection(cube))
+}
+
+/// Count how many steps include the given point, regardless of whether they turn it on or off
+pub fn toggle_count(reboot_steps: &[RebootStep], point: (isize, isize, isize)) -> usize {
+    reboot_steps
+        .iter()
+        .filter(|s| s.cube.contains(point))
+        .count()
+}
+
 fn parse_number(input: &str) -> IResult<&str, isize> {
     map_res(
         recognize(pair(opt(tag("-")), many1(one_of("0123456789")))),
@@ -116,30 +175,38 @@ fn parse_range(input: &str) -> IResult<&str, RangeInclusive<isize>> {
     )(input)
 }
 
-fn parse_reboot_step(input: &str) -> Result<RebootStep, nom::Err<nom::error::Error<String>>> {
-    map(
+/// Parse a single reboot step, such as `on x=10..12,y=10..12,z=10..12`
+pub fn parse_reboot_step(input: &str) -> Result<RebootStep, nom::Err<nom::error::Error<String>>> {
+    // Reversed ranges are rejected the same way as when deserializing
+    map_opt(
         tuple((
             alt((value(true, tag("on")), value(false, tag("off")))),
             preceded(tag(" x="), parse_range),
             preceded(tag(",y="), parse_range),
             preceded(tag(",z="), parse_range),
         )),
-        |(state, x, y, z)| RebootStep {
-            turn_on: state,
-            cube: CubeSelection { x, y, z },
+        |(state, x, y, z)| {
+            Some(RebootStep {
+                turn_on: state,
+                cube: CubeSelection::new(x, y, z)?,
+            })
         },
     )(input)
     .map(|(_, step)| step)
     .map_err(|e: nom::Err<nom::error::Error<&str>>| e.to_owned())
 }
 
-fn part_a(reboot_steps: &[RebootStep]) -> usize {
-    // Since we're only looking at one million cubes we can brute force
+/// Count the cubes that are on within the given region by tracking each cube individually
+pub fn part_a_region(reboot_steps: &[RebootStep], region: &CubeSelection) -> usize {
     let mut on = HashSet::new();
     for step in reboot_steps {
-        for z in (-50).max(*step.cube.z.start())..=50.min(*step.cube.z.end()) {
-            for y in (-50).max(*step.cube.y.start())..=50.min(*step.cube.y.end()) {
-                for x in (-50).max(*step.cube.x.start())..=50.min(*step.cube.x.end()) {
+        let cube = match step.cube.intersection(region) {
+            Some(c) => c,
+            None => continue,
+        };
+        for z in cube.z.clone() {
+            for y in cube.y.clone() {
+                for x in cube.x.clone() {
                     if step.turn_on {
                         on.insert((x, y, z));
                     } else {
@@ -152,33 +219,109 @@ fn part_a(reboot_steps: &[RebootStep]) -> usize {
     on.len()
 }
 
-fn part_b(reboot_steps: &[RebootStep]) -> usize {
+fn part_a(reboot_steps: &[RebootStep]) -> usize {
+    // Since we're only looking at one million cubes we can brute force
+    let region = CubeSelection::new(-50..=50, -50..=50, -50..=50).unwrap();
+    part_a_region(reboot_steps, &region)
+}
+
+/// Return disjoint selections covering every cube that is on after all steps. If a region is
+/// given, only the cubes within it are included.
+fn on_cubes(reboot_steps: &[RebootStep], region: Option<&CubeSelection>) -> Vec<CubeSelection> {
     let mut on: Vec<CubeSelection> = Vec::new();
     for step in reboot_steps {
+        let cube = match region {
+            Some(region) => match step.cube.intersection(region) {
+                Some(c) => c,
+                None => continue,
+            },
+            None => step.cube.clone(),
+        };
         on = on
             .into_iter()
-            .flat_map(|c| c.difference(&step.cube).into_iter())
+            .flat_map(|c| c.difference(&cube).into_iter())
             .collect();
         if step.turn_on {
-            on.push(step.cube.clone());
+            on.push(cube);
         }
     }
-    on.iter().map(|c| c.len()).sum::<usize>()
+    on
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let reboot_steps = io::BufReader::new(File::open(path)?)
+/// Count the cubes that are on by tracking disjoint selections. If a region is given, only the
+/// cubes within it are counted.
+fn count_on_in_region(reboot_steps: &[RebootStep], region: Option<&CubeSelection>) -> usize {
+    on_cubes(reboot_steps, region)
+        .iter()
+        .map(|c| c.len())
+        .sum::<usize>()
+}
+
+/// The state of the reactor after all reboot steps, for answering many queries about it
+#[derive(Debug, Clone)]
+pub struct ReactorState {
+    /// Disjoint selections of cubes that are on, sorted by where they start along the X axis
+    cubes: Vec<CubeSelection>,
+
+    /// The widest selection along the X axis
+    max_width: isize,
+}
+
+impl ReactorState {
+    pub fn new(reboot_steps: &[RebootStep]) -> Self {
+        let mut cubes = on_cubes(reboot_steps, None);
+        cubes.sort_unstable_by_key(|c| *c.x.start());
+        let max_width = cubes
+            .iter()
+            .map(|c| c.x.end() - c.x.start())
+            .max()
+            .unwrap_or(0);
+        Self { cubes, max_width }
+    }
+
+    /// Return true if the cube at the given point is on
+    pub fn is_on(&self, point: (isize, isize, isize)) -> bool {
+        // Only selections that start at most `max_width` before the point can reach it
+        let x = point.0;
+        let first = self
+            .cubes
+            .partition_point(|c| *c.x.start() < x - self.max_width);
+        let last = self.cubes.partition_point(|c| *c.x.start() <= x);
+        self.cubes[first..last].iter().any(|c| c.contains(point))
+    }
+
+    /// The disjoint selections of cubes that are on
+    pub fn cubes(&self) -> &[CubeSelection] {
+        &self.cubes
+    }
+
+    /// The number of cubes that are on
+    pub fn count_on(&self) -> usize {
+        self.cubes.iter().map(|c| c.len()).sum()
+    }
+}
+
+fn part_b(reboot_steps: &[RebootStep]) -> usize {
+    count_on_in_region(reboot_steps, None)
+}
+
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let reboot_steps = input
         .lines()
-        .map(|lr| Ok(parse_reboot_step(&lr?)?))
+        .map(|l| Ok(parse_reboot_step(l)?))
         .collect::<Result<Vec<_>>>()?;
     Ok((part_a(&reboot_steps), Some(part_b(&reboot_steps))))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const EXAMPLE: &'static [&str] = &[
+    const EXAMPLE: &[&str] = &[
         "on x=-5..47,y=-31..22,z=-19..33",
         "on x=-44..5,y=-27..21,z=-14..35",
         "on x=-49..-1,y=-11..42,z=-10..38",
@@ -241,6 +384,19 @@ mod tests {
         "off x=-93533..-4276,y=-16170..68771,z=-104985..-24507",
     ];
 
+    /// The small example from the puzzle description, which turns on 39 cubes
+    fn small_example() -> Result<Vec<RebootStep>, nom::Err<nom::error::Error<String>>> {
+        [
+            "on x=10..12,y=10..12,z=10..12",
+            "on x=11..13,y=11..13,z=11..13",
+            "off x=9..11,y=9..11,z=9..11",
+            "on x=10..10,y=10..10,z=10..10",
+        ]
+        .iter()
+        .map(|l| parse_reboot_step(l))
+        .collect()
+    }
+
     #[test]
     fn test_example() -> Result<()> {
         let steps = EXAMPLE
@@ -251,4 +407,156 @@ mod tests {
         assert_eq!(part_b(&steps), 2758514936282235);
         Ok(())
     }
+
+    #[test]
+    fn test_part_a_region() -> Result<()> {
+        let steps = small_example()?;
+
+        let count_in = |region: &CubeSelection| count_on_in_region(&steps, Some(region));
+
+        let everything = CubeSelection::new(-100..=100, -100..=100, -100..=100).unwrap();
+        assert_eq!(part_a_region(&steps, &everything), 39);
+        assert_eq!(count_in(&everything), 39);
+
+        let corner = CubeSelection::new(11..=20, 11..=20, 9..=12).unwrap();
+        assert_eq!(part_a_region(&steps, &corner), count_in(&corner));
+        assert_eq!(part_a_region(&steps, &corner), 20);
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_on_in_region() -> Result<()> {
+        let steps = EXAMPLE
+            .iter()
+            .map(|l| parse_reboot_step(l))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The brute force and the interval based approach must agree on the initialization region
+        let init_region = CubeSelection::new(-50..=50, -50..=50, -50..=50).unwrap();
+        assert_eq!(count_on_in_region(&steps, Some(&init_region)), 474140);
+        assert_eq!(
+            count_on_in_region(&steps, Some(&init_region)),
+            part_a(&steps)
+        );
+        assert_eq!(count_on_in_region(&steps, None), part_b(&steps));
+        Ok(())
+    }
+
+    #[test]
+    fn test_common_region() -> Result<()> {
+        let parse = |steps: &[&str]| {
+            steps
+                .iter()
+                .map(|l| parse_reboot_step(l))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        // Steps that turn cubes off don't affect the common region
+        let steps = parse(&[
+            "on x=0..10,y=0..10,z=0..10",
+            "off x=100..200,y=100..200,z=100..200",
+            "on x=5..20,y=-5..7,z=3..3",
+            "on x=-10..8,y=6..10,z=0..10",
+        ])?;
+        assert_eq!(
+            common_region(&steps),
+            CubeSelection::new(5..=8, 6..=7, 3..=3)
+        );
+
+        let steps = parse(&["on x=0..10,y=0..10,z=0..10", "on x=11..20,y=0..10,z=0..10"])?;
+        assert_eq!(common_region(&steps), None);
+        assert_eq!(common_region(&[]), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_reversed_range() {
+        assert!(parse_reboot_step("on x=5..10,y=0..0,z=0..0").is_ok());
+        assert!(parse_reboot_step("on x=10..5,y=0..0,z=0..0").is_err());
+        assert!(parse_reboot_step("off x=0..0,y=0..0,z=1..-1").is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() -> Result<()> {
+        let cube = CubeSelection::new(-20..=26, -36..=17, -47..=7).unwrap();
+        let json = serde_json::to_string(&cube)?;
+        assert_eq!(serde_json::from_str::<CubeSelection>(&json)?, cube);
+
+        let empty = json.replacen(r#""start":-20,"end":26"#, r#""start":5,"end":1"#, 1);
+        assert_ne!(empty, json);
+        assert!(serde_json::from_str::<CubeSelection>(&empty).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_volume_of_union() {
+        let a = CubeSelection::new(0..=2, 0..=2, 0..=2).unwrap();
+        let b = CubeSelection::new(1..=3, 1..=3, -1..=2).unwrap();
+        assert!(a.contains((0, 0, 0)));
+        assert!(a.contains((2, 1, 2)));
+        assert!(!a.contains((2, 3, 2)));
+        assert!(!b.contains((0, 1, 1)));
+
+        // Inclusion-exclusion: |A| + |B| - |A ∩ B|
+        let overlap = a.intersection(&b).unwrap();
+        let expected = (a.len() + b.len() - overlap.len()) as u128;
+        assert_eq!(expected, 27 + 36 - 12);
+        assert_eq!(volume_of_union(&[a.clone(), b.clone()]), expected);
+        assert_eq!(volume_of_union(&[b, a.clone(), a]), expected);
+        assert_eq!(volume_of_union(&[]), 0);
+
+        // The volume of a single selection wouldn't fit in a u64
+        let huge = CubeSelection::new(
+            -1_000_000_000..=1_000_000_000,
+            0..=4_000_000_000,
+            0..=4_000_000_000,
+        )
+        .unwrap();
+        let side = 2_000_000_001;
+        assert_eq!(
+            volume_of_union(&[huge]),
+            side * 4_000_000_001 * 4_000_000_001
+        );
+    }
+
+    #[test]
+    fn test_toggle_count() -> Result<()> {
+        let steps = small_example()?;
+
+        // The corner was turned on, off and then on again
+        assert_eq!(toggle_count(&steps, (10, 10, 10)), 3);
+        assert_eq!(toggle_count(&steps, (11, 11, 11)), 3);
+        assert_eq!(toggle_count(&steps, (13, 13, 13)), 1);
+        assert_eq!(toggle_count(&steps, (0, 0, 0)), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reactor_state() -> Result<()> {
+        let steps = small_example()?;
+        let state = ReactorState::new(&steps);
+        assert_eq!(state.count_on(), 39);
+
+        assert!(state.is_on((10, 10, 10)));
+        assert!(!state.is_on((11, 11, 11)));
+        assert!(state.is_on((12, 12, 12)));
+        assert!(state.is_on((13, 13, 13)));
+        assert!(!state.is_on((14, 13, 13)));
+        assert!(!state.is_on((0, 0, 0)));
+
+        // Compare against checking every selection
+        let on = on_cubes(&steps, None);
+        for x in 8..=15 {
+            for y in 8..=15 {
+                for z in 8..=15 {
+                    let expected = on.iter().any(|c| c.contains((x, y, z)));
+                    assert_eq!(state.is_on((x, y, z)), expected);
+                }
+            }
+        }
+
+        assert!(!ReactorState::new(&[]).is_on((0, 0, 0)));
+        Ok(())
+    }
 }
@@ -1,49 +1,53 @@
+use crate::search;
 use anyhow::{anyhow, Result};
-use std::cmp::Reverse;
-use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::collections::{HashSet, VecDeque};
 use std::fmt;
+use std::ops::Add;
 use std::path::Path;
 
-/// We need this exotic data structure since we can't store types that don't implement Ord directly
-/// in a BinaryHeap
-#[derive(Debug, Clone)]
-struct PriorityQueue<P, T> {
-    heap: BinaryHeap<(P, Reverse<usize>)>,
-    values: HashMap<usize, T>,
-    next_index: usize,
+/// The energy it takes for amber, bronze, copper and desert amphipods to take a single step
+pub const DEFAULT_COSTS: [u64; 4] = [1, 10, 100, 1000];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Amphipod {
+    Amber,
+    Bronze,
+    Copper,
+    Desert,
 }
 
-impl<P, T> PriorityQueue<P, T>
-where
-    P: Ord,
-{
-    fn new() -> Self {
-        Self {
-            heap: BinaryHeap::new(),
-            values: HashMap::new(),
-            next_index: 0,
-        }
-    }
+/// Energy that keeps track of whether it has overflowed. Overflowed energy is larger than any other
+/// energy, so the search only ends up with it when every solution overflows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Energy {
+    Finite(u64),
+    Overflow,
+}
 
-    fn push(&mut self, v: T, p: P) {
-        self.heap.push((p, Reverse(self.next_index)));
-        self.values.insert(self.next_index, v);
-        self.next_index += 1;
+impl Energy {
+    /// The energy it takes to walk the given number of steps at the given cost per step
+    fn of_steps(steps: u64, cost: u64) -> Self {
+        steps.checked_mul(cost).map_or(Self::Overflow, Self::Finite)
     }
+}
 
-    fn pop(&mut self) -> Option<(T, P)> {
-        self.heap
-            .pop()
-            .map(|(p, Reverse(k))| (self.values.remove(&k).unwrap(), p))
+impl Default for Energy {
+    fn default() -> Self {
+        Self::Finite(0)
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Amphipod {
-    Amber,
-    Bronze,
-    Copper,
-    Desert,
+impl Add for Energy {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Finite(a), Self::Finite(b)) => {
+                a.checked_add(b).map_or(Self::Overflow, Self::Finite)
+            }
+            _ => Self::Overflow,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -65,12 +69,23 @@ impl Amphipod {
         }
     }
 
-    const fn energy(&self) -> usize {
+    /// The X coordinate of the room this amphipod belongs in
+    const fn room_x(&self) -> usize {
+        match self {
+            Self::Amber => 3,
+            Self::Bronze => 5,
+            Self::Copper => 7,
+            Self::Desert => 9,
+        }
+    }
+
+    /// The energy it takes for this amphipod to take a single step, according to the cost table
+    const fn cost(&self, costs: &[u64; 4]) -> u64 {
         match self {
-            Self::Amber => 1,
-            Self::Bronze => 10,
-            Self::Copper => 100,
-            Self::Desert => 1000,
+            Self::Amber => costs[0],
+            Self::Bronze => costs[1],
+            Self::Copper => costs[2],
+            Self::Desert => costs[3],
         }
     }
 }
@@ -91,21 +106,60 @@ impl Cell {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct Burrow {
+pub struct Burrow {
     // We can't use HashMap here since it doesn't implement Hash
     cells: Vec<Vec<Cell>>,
 }
 
 impl Burrow {
-    fn target() -> Self {
-        let mut target_str = String::new();
-        target_str.push_str("#############\n");
-        target_str.push_str("#...........#\n");
-        target_str.push_str("###A#B#C#D###\n");
-        target_str.push_str("  #A#B#C#D#\n");
-        target_str.push_str("  #########\n");
+    /// Build a burrow with the given hallway width and room contents, where every row contains
+    /// the amphipods in the rooms from left to right. The first row is closest to the hallway.
+    pub fn from_rows(hallway_width: usize, room_contents: &[[Amphipod; 4]]) -> Result<Self> {
+        // The rooms are always located at the same positions so the hallway must reach them all
+        if hallway_width < Amphipod::Desert.room_x() {
+            return Err(anyhow!("The hallway must be at least 9 cells wide"));
+        }
 
-        Self::from_str(&target_str).unwrap()
+        let mut cells = vec![
+            vec![Cell::Wall; hallway_width + 2],
+            std::iter::once(Cell::Wall)
+                .chain(std::iter::repeat_n(Cell::Empty, hallway_width))
+                .chain(std::iter::once(Cell::Wall))
+                .collect(),
+        ];
+        for (i, row) in room_contents.iter().enumerate() {
+            // The top row of the rooms extend the walls all the way to the sides
+            let (padding, trailing_walls) = if i == 0 {
+                (Cell::Wall, hallway_width - 9)
+            } else {
+                (Cell::Space, 0)
+            };
+            let mut cells_row = vec![padding, padding, Cell::Wall];
+            for &amphipod in row {
+                cells_row.extend([Cell::Amphipod(amphipod), Cell::Wall]);
+            }
+            cells_row.extend(std::iter::repeat_n(Cell::Wall, trailing_walls));
+            cells.push(cells_row);
+        }
+        cells.push(
+            [Cell::Space, Cell::Space]
+                .into_iter()
+                .chain(std::iter::repeat_n(Cell::Wall, 9))
+                .collect(),
+        );
+        Ok(Self { cells })
+    }
+
+    /// The organized burrow where every room is `depth` amphipods deep
+    pub fn target_for_depth(depth: usize) -> Self {
+        use Amphipod::*;
+        Self::from_rows(11, &vec![[Amber, Bronze, Copper, Desert]; depth]).unwrap()
+    }
+
+    /// True if every amphipod is inside its own room
+    pub fn is_solved(&self) -> bool {
+        self.find_amphipods()
+            .all(|(x, y, amphipod)| x == amphipod.room_x() && y > 1)
     }
 
     fn get(&self, x: usize, y: usize) -> Option<Cell> {
@@ -135,18 +189,43 @@ impl Burrow {
     }
 
     fn is_room(x: usize, y: usize) -> bool {
-        matches!(
-            (x, y),
-            (3, 2) | (3, 3) | (5, 2) | (5, 3) | (7, 2) | (7, 3) | (9, 2) | (9, 3)
-        )
+        matches!(x, 3 | 5 | 7 | 9) && y > 1
+    }
+
+    /// The number of amphipods that fit in each room
+    fn room_depth(&self) -> usize {
+        (2..)
+            .take_while(|&y| matches!(self.get(3, y), Some(Cell::Empty | Cell::Amphipod(_))))
+            .count()
+    }
+
+    /// Return the position the amphipod should move to when entering its room, which is the
+    /// deepest empty cell. Returns `None` if the room contains amphipods that don't belong there,
+    /// since they must leave before anyone can enter.
+    fn room_target(&self, amphipod: Amphipod, depth: usize) -> Option<(usize, usize)> {
+        let room_x = amphipod.room_x();
+        for y in (2..2 + depth).rev() {
+            match self.get(room_x, y)? {
+                Cell::Amphipod(a) if a == amphipod => continue,
+                Cell::Empty => return Some((room_x, y)),
+                _ => return None,
+            }
+        }
+        None
     }
 
-    fn is_hallway(x: usize, y: usize) -> bool {
+    /// True if the amphipod is in its own room with only amphipods of the same type below it
+    fn is_home(&self, x: usize, y: usize, amphipod: Amphipod, depth: usize) -> bool {
+        x == amphipod.room_x()
+            && y > 1
+            && (y + 1..2 + depth)
+                .all(|y| matches!(self.get(x, y), Some(Cell::Amphipod(a)) if a == amphipod))
+    }
+
+    /// True if the cell is in the hallway and an amphipod may stop there
+    fn is_hallway(&self, x: usize, y: usize) -> bool {
         // We exclude the cells right outside a room as we're not allowed to stop there
-        matches!(
-            (x, y),
-            (1, 1) | (2, 1) | (4, 1) | (6, 1) | (8, 1) | (10, 1) | (11, 1)
-        )
+        y == 1 && !matches!(x, 3 | 5 | 7 | 9) && self.get(x, y).is_some_and(|c| c != Cell::Wall)
     }
 
     /// Return a list of all reachable cells from the current position and the number of steps to
@@ -189,11 +268,52 @@ impl Burrow {
         reachable_cells
     }
 
+    /// Return all burrows that can be reached by moving a single amphipod, together with the energy
+    /// it takes when each type of amphipod uses the given energy per step
+    pub fn legal_moves(&self, costs: &[u64; 4]) -> Vec<(Self, Energy)> {
+        let mut moves = Vec::new();
+        let depth = self.room_depth();
+
+        // Find all amphipods and explore what paths they can take
+        for (x, y, amphipod) in self.find_amphipods() {
+            // If we're already in the right room we shouldn't go back out again, unless we must
+            // make room for another amphipod to leave
+            if self.is_home(x, y, amphipod, depth) {
+                continue;
+            }
+            let target = self.room_target(amphipod, depth);
+
+            // Generate all new burrow configurations based on
+            for (nx, ny, steps) in self.find_reachable_cells(x, y) {
+                // If we are currently in a room we can only step out into the hallway
+                if Self::is_room(x, y) && !self.is_hallway(nx, ny) {
+                    continue;
+                }
+
+                // If we are in the hallway we must go inside the right room in the right spot
+                if self.is_hallway(x, y) && Some((nx, ny)) != target {
+                    continue;
+                }
+
+                let mut new_burrow = self.clone();
+                let cell = new_burrow.take(x, y).unwrap();
+                new_burrow.set(nx, ny, cell);
+                let energy = Energy::of_steps(steps as u64, amphipod.cost(costs));
+                moves.push((new_burrow, energy));
+            }
+        }
+        moves
+    }
+
+    /// Parse a burrow from its drawing. Trailing whitespace, including carriage returns, is ignored
+    /// so rows never end with spaces no matter how the input was edited
     fn from_str(input: &str) -> Result<Self> {
         let cells = input
+            .trim_end()
             .lines()
             .map(|line| {
-                line.chars()
+                line.trim_end()
+                    .chars()
                     .map(Cell::from_char)
                     .collect::<Result<Vec<_>>>()
             })
@@ -223,94 +343,359 @@ impl fmt::Display for Burrow {
     }
 }
 
-fn part_a(burrow: Burrow) -> Option<usize> {
-    let target = Burrow::target();
-
-    // We use this exotic priority queue instead of binary heap since Burrow can't implement Ord
-    let mut queue = PriorityQueue::new();
-    let mut visited = HashSet::new();
-    queue.push(burrow, Reverse(0usize));
-
-    while let Some((burrow, Reverse(energy))) = queue.pop() {
-        println!("{}{}\n", &burrow, energy);
-        if burrow == target {
-            return Some(energy);
-        }
-        if !visited.insert(burrow.clone()) {
-            continue;
-        }
-
-        // Find all amphipods and explore what paths they can take
-        for (x, y, amphipod) in burrow.find_amphipods() {
-            // Check which room this amphipod belongs in
-            let (outer_target, inner_target) = match amphipod {
-                Amphipod::Amber => ((3, 2), (3, 3)),
-                Amphipod::Bronze => ((5, 2), (5, 3)),
-                Amphipod::Copper => ((7, 2), (7, 3)),
-                Amphipod::Desert => ((9, 2), (9, 3)),
-            };
-
-            // If we have already reached the inner position we shouldn't go back out again
-            if (x, y) == inner_target {
-                continue;
-            }
-            let inner_target_done = matches!(
-                burrow.get(inner_target.0, inner_target.1),
-                Some(Cell::Amphipod(a)) if a == amphipod,
-            );
-
-            if inner_target_done && (x, y) == outer_target {
-                continue;
+/// Admissible estimate of the energy required to move all amphipods into their rooms
+///
+/// Every amphipod that isn't in its own room must at least walk up into the hallway, across to its
+/// room and one step down into it. Amphipods that have to move out of the way of others are not
+/// accounted for, which keeps the estimate from ever overshooting the actual cost.
+fn estimate_remaining(burrow: &Burrow, costs: &[u64; 4]) -> Energy {
+    burrow
+        .find_amphipods()
+        .map(|(x, y, amphipod)| {
+            let room_x = amphipod.room_x();
+            if x == room_x && y > 1 {
+                return Energy::default();
             }
+            let steps = (y - 1) + x.abs_diff(room_x) + 1;
+            Energy::of_steps(steps as u64, amphipod.cost(costs))
+        })
+        .fold(Energy::default(), Energy::add)
+}
 
-            // Generate all new burrow configurations based on
-            for (nx, ny, steps) in burrow.find_reachable_cells(x, y) {
-                // If we are currently in a room we can only step out into the hallway
-                if Burrow::is_room(x, y) && !Burrow::is_hallway(nx, ny) {
-                    continue;
-                }
+/// Find the lowest energy required to organize the burrow with the given costs, ordering the
+/// search by the given estimate of the remaining energy. Returns the energy and the number of
+/// expanded states, or an error if more than `max_states` states had to be expanded or if the
+/// energy of every solution is too large to fit in a `u64`.
+fn search(
+    burrow: Burrow,
+    costs: &[u64; 4],
+    estimate: fn(&Burrow, &[u64; 4]) -> Energy,
+    max_states: Option<usize>,
+) -> Result<Option<(u64, usize)>> {
+    let solution = search::a_star_limited(
+        burrow,
+        Burrow::is_solved,
+        |b| b.legal_moves(costs),
+        |b| estimate(b, costs),
+        max_states,
+    )?;
+    match solution {
+        Some((Energy::Finite(energy), expanded)) => Ok(Some((energy, expanded))),
+        Some((Energy::Overflow, _)) => Err(anyhow!("Energy overflowed with costs {:?}", costs)),
+        None => Ok(None),
+    }
+}
 
-                // If we are in the hallway we must go inside the right room in the right spot
-                if Burrow::is_hallway(x, y)
-                    && ((!inner_target_done && (nx, ny) != inner_target)
-                        || (inner_target_done && (nx, ny) != outer_target))
-                {
-                    continue;
-                }
+/// Find the lowest energy required to organize the burrow when amber, bronze, copper and desert
+/// amphipods use the given energy per step. Returns `None` if there is no solution, or an error if
+/// more than `max_states` states had to be expanded or if the energy of every solution is too
+/// large to fit in a `u64`
+pub fn solve_with_costs(
+    burrow: Burrow,
+    costs: [u64; 4],
+    max_states: Option<usize>,
+) -> Result<Option<u64>> {
+    Ok(search(burrow, &costs, estimate_remaining, max_states)?.map(|(energy, _)| energy))
+}
 
-                let mut new_burrow = burrow.clone();
-                let cell = new_burrow.take(x, y).unwrap();
-                new_burrow.set(nx, ny, cell);
+fn part_a(burrow: Burrow, max_states: Option<usize>) -> Result<u64> {
+    solve_with_costs(burrow, DEFAULT_COSTS, max_states)?
+        .ok_or_else(|| anyhow!("Can't find a solution for part A"))
+}
 
-                if visited.contains(&new_burrow) {
-                    continue;
-                }
+pub fn solve(input: &str) -> Result<(u64, Option<u64>)> {
+    solve_limited(input, None)
+}
 
-                queue.push(
-                    new_burrow.clone(),
-                    Reverse(energy + steps * amphipod.energy()),
-                );
-            }
-        }
-    }
-    None
+/// Same as `solve`, but gives up with an error once more than `max_states` states have been
+/// expanded
+pub fn solve_limited(input: &str, max_states: Option<usize>) -> Result<(u64, Option<u64>)> {
+    let burrow = Burrow::from_str(input)?;
+    Ok((part_a(burrow, max_states)?, None))
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let input = std::fs::read_to_string(path)?;
-    let burrow = Burrow::from_str(&input)?;
-    Ok((
-        part_a(burrow).ok_or_else(|| anyhow!("Can't find a solution for part A"))?,
-        None,
-    ))
+pub fn main(path: &Path) -> Result<(u64, Option<u64>)> {
+    solve(&std::fs::read_to_string(path)?)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn example() -> Burrow {
+        let mut example = String::new();
+        example.push_str("#############\n");
+        example.push_str("#...........#\n");
+        example.push_str("###B#C#B#D###\n");
+        example.push_str("  #A#D#C#A#\n");
+        example.push_str("  #########\n");
+        Burrow::from_str(&example).unwrap()
+    }
+
+    #[test]
+    fn test_from_str_whitespace() {
+        let mut example_crlf = String::new();
+        example_crlf.push_str("#############\r\n");
+        example_crlf.push_str("#...........#  \r\n");
+        example_crlf.push_str("###B#C#B#D###\r\n");
+        example_crlf.push_str("  #A#D#C#A#   \r\n");
+        example_crlf.push_str("  #########\r\n");
+        example_crlf.push_str("\r\n");
+        assert_eq!(Burrow::from_str(&example_crlf).unwrap(), example());
+    }
+
+    #[test]
+    fn test_target_for_depth() {
+        let mut target_str = String::new();
+        target_str.push_str("#############\n");
+        target_str.push_str("#...........#\n");
+        target_str.push_str("###A#B#C#D###\n");
+        target_str.push_str("  #A#B#C#D#\n");
+        target_str.push_str("  #########\n");
+
+        let target = Burrow::target_for_depth(2);
+        assert_eq!(target, Burrow::from_str(&target_str).unwrap());
+        assert!(target.is_solved());
+
+        let deep_target = Burrow::target_for_depth(4);
+        assert!(deep_target.is_solved());
+        assert_eq!(deep_target.find_amphipods().count(), 16);
+        assert_eq!(deep_target.to_string().lines().count(), 7);
+
+        assert!(!example().is_solved());
+    }
+
+    #[test]
+    fn test_from_rows() {
+        use Amphipod::*;
+        let burrow = Burrow::from_rows(
+            11,
+            &[
+                [Bronze, Copper, Bronze, Desert],
+                [Amber, Desert, Copper, Amber],
+            ],
+        )
+        .unwrap();
+        assert_eq!(burrow, example());
+        assert!(Burrow::from_rows(8, &[[Amber, Bronze, Copper, Desert]]).is_err());
+    }
+
+    #[test]
+    fn test_estimate_remaining() {
+        let estimate = |burrow: &Burrow| estimate_remaining(burrow, &DEFAULT_COSTS);
+        assert_eq!(estimate(&Burrow::target_for_depth(2)), Energy::Finite(0));
+
+        // Only the amphipods outside their own room are counted. Each needs to step out into the
+        // hallway, walk to its room and step down into it
+        let bronze = 2 * (1 + 2 + 1) * 10;
+        let copper = (1 + 2 + 1) * 100;
+        let desert = (2 + 4 + 1) * 1000;
+        let amber = 2 + 6 + 1;
+        assert_eq!(
+            estimate(&example()),
+            Energy::Finite(amber + bronze + copper + desert)
+        );
+    }
+
     #[test]
     fn test_example() -> Result<()> {
+        let no_estimate = |_: &Burrow, _: &[u64; 4]| Energy::default();
+        let (dijkstra_energy, dijkstra_expanded) =
+            search(example(), &DEFAULT_COSTS, no_estimate, None)?.unwrap();
+        let (a_star_energy, a_star_expanded) =
+            search(example(), &DEFAULT_COSTS, estimate_remaining, None)?.unwrap();
+        assert_eq!(dijkstra_energy, 12521);
+        assert_eq!(a_star_energy, 12521);
+        assert!(a_star_expanded < dijkstra_expanded);
+        assert_eq!(part_a(example(), None)?, 12521);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deep_rooms() -> Result<()> {
+        use Amphipod::*;
+        let burrow = Burrow::from_rows(
+            11,
+            &[
+                [Bronze, Copper, Bronze, Desert],
+                [Desert, Copper, Bronze, Amber],
+                [Desert, Bronze, Amber, Copper],
+                [Amber, Desert, Copper, Amber],
+            ],
+        )?;
+        assert_eq!(burrow.room_depth(), 4);
+        assert_eq!(example().room_depth(), 2);
+        assert_eq!(part_a(burrow, None)?, 44169);
+        Ok(())
+    }
+
+    #[test]
+    fn test_solve_with_costs() -> Result<()> {
+        assert_eq!(
+            solve_with_costs(example(), DEFAULT_COSTS, None)?,
+            Some(12521)
+        );
+
+        // Every move costs twice as much, so the cheapest solution does too
+        let doubled = DEFAULT_COSTS.map(|c| 2 * c);
+        assert_eq!(solve_with_costs(example(), doubled, None)?, Some(2 * 12521));
+        Ok(())
+    }
+
+    #[test]
+    fn test_energy_overflow() {
+        let err = solve_with_costs(example(), [u64::MAX / 2; 4], None).unwrap_err();
+        assert!(err.to_string().starts_with("Energy overflowed"));
+
+        assert_eq!(Energy::of_steps(3, u64::MAX / 2), Energy::Overflow);
+        assert_eq!(
+            Energy::Finite(u64::MAX / 2) + Energy::Finite(u64::MAX / 2),
+            Energy::Finite(u64::MAX - 1)
+        );
+        assert_eq!(
+            Energy::Finite(u64::MAX / 2) + Energy::Finite(u64::MAX / 2 + 2),
+            Energy::Overflow
+        );
+        assert!(Energy::Overflow > Energy::Finite(u64::MAX));
+    }
+
+    #[test]
+    fn test_energy() -> Result<()> {
+        // The most expensive first move is the desert amphipod walking nine steps to the far left
+        let moves = example().legal_moves(&DEFAULT_COSTS);
+        assert_eq!(
+            moves.iter().map(|&(_, energy)| energy).max(),
+            Some(Energy::Finite(9000))
+        );
+
+        // Energy doesn't depend on the platform's pointer width, so it won't overflow even when
+        // the total exceeds what fits in 32 bits
+        let costs = DEFAULT_COSTS.map(|c| c * 1_000_000);
+        let energy = solve_with_costs(example(), costs, None)?.unwrap();
+        assert!(energy > u64::from(u32::MAX));
+        assert_eq!(energy, 12521 * 1_000_000);
         Ok(())
     }
+
+    #[test]
+    fn test_max_states() -> Result<()> {
+        let err = part_a(example(), Some(10)).unwrap_err();
+        assert_eq!(err.to_string(), "Search exceeded 10 states");
+
+        let input = example().to_string();
+        let err = solve_limited(&input, Some(10)).unwrap_err();
+        assert_eq!(err.to_string(), "Search exceeded 10 states");
+        assert_eq!(solve_limited(&input, Some(100_000))?, (12521, None));
+        Ok(())
+    }
+
+    /// Return the destination and energy of every legal move for the amphipod at the given cell
+    fn moves_from(burrow: &Burrow, x: usize, y: usize) -> Vec<((usize, usize), Energy)> {
+        burrow
+            .legal_moves(&DEFAULT_COSTS)
+            .into_iter()
+            .filter(|(next, _)| next.get(x, y) == Some(Cell::Empty))
+            .map(|(next, energy)| {
+                let (nx, ny, _) = next
+                    .find_amphipods()
+                    .find(|&(nx, ny, _)| burrow.get(nx, ny) == Some(Cell::Empty))
+                    .unwrap();
+                ((nx, ny), energy)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_legal_moves_home() {
+        // The amber amphipod at the bottom of its own room stays put
+        assert!(moves_from(&example(), 3, 3).is_empty());
+
+        // So does one on top of it, but not one on top of a stranger
+        let mut burrow = String::new();
+        burrow.push_str("#############\n");
+        burrow.push_str("#...........#\n");
+        burrow.push_str("###A#C#B#D###\n");
+        burrow.push_str("  #A#D#C#B#\n");
+        burrow.push_str("  #########\n");
+        let burrow = Burrow::from_str(&burrow).unwrap();
+        assert!(moves_from(&burrow, 3, 2).is_empty());
+        assert_eq!(moves_from(&burrow, 5, 2).len(), 7);
+    }
+
+    #[test]
+    fn test_legal_moves_above_room() {
+        // Amphipods leaving a room may stop anywhere in the hallway except right above a room
+        let destinations = example()
+            .legal_moves(&DEFAULT_COSTS)
+            .into_iter()
+            .flat_map(|(next, _)| next.find_amphipods().collect::<Vec<_>>())
+            .filter(|&(_, y, _)| y == 1)
+            .map(|(x, _, _)| x)
+            .collect::<HashSet<_>>();
+        assert_eq!(destinations, [1, 2, 4, 6, 8, 10, 11].into_iter().collect());
+    }
+
+    #[test]
+    fn test_wide_hallway() -> Result<()> {
+        use Amphipod::*;
+        let rows = [
+            [Bronze, Copper, Bronze, Desert],
+            [Amber, Desert, Copper, Amber],
+        ];
+
+        // The extra cells to the right can be used to stop in as well
+        let wide = Burrow::from_rows(13, &rows)?;
+        let destinations = wide
+            .legal_moves(&DEFAULT_COSTS)
+            .into_iter()
+            .flat_map(|(next, _)| next.find_amphipods().collect::<Vec<_>>())
+            .filter(|&(_, y, _)| y == 1)
+            .map(|(x, _, _)| x)
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            destinations,
+            [1, 2, 4, 6, 8, 10, 11, 12, 13].into_iter().collect()
+        );
+
+        // A narrow hallway only leaves room at the left
+        let narrow = Burrow::from_rows(9, &rows)?;
+        assert!(narrow.is_hallway(1, 1) && !narrow.is_hallway(9, 1));
+        assert!(!narrow.is_hallway(10, 1));
+        assert_eq!(part_a(wide, None)?, part_a(example(), None)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_legal_moves_into_room() {
+        // The amber amphipod in the hallway can't enter its room while a stranger is in it
+        let mut burrow = String::new();
+        burrow.push_str("#############\n");
+        burrow.push_str("#A..........#\n");
+        burrow.push_str("###.#C#B#D###\n");
+        burrow.push_str("  #B#D#C#A#\n");
+        burrow.push_str("  #########\n");
+        let burrow = Burrow::from_str(&burrow).unwrap();
+        assert!(moves_from(&burrow, 1, 1).is_empty());
+
+        // Once the room only contains its own kind the amphipod goes to the first free spot
+        let mut burrow = String::new();
+        burrow.push_str("#############\n");
+        burrow.push_str("#A..........#\n");
+        burrow.push_str("###.#C#B#D###\n");
+        burrow.push_str("  #A#D#C#B#\n");
+        burrow.push_str("  #########\n");
+        let burrow = Burrow::from_str(&burrow).unwrap();
+        assert_eq!(moves_from(&burrow, 1, 1), [((3, 2), Energy::Finite(3))]);
+
+        // An empty room is entered all the way to the bottom
+        let mut burrow = String::new();
+        burrow.push_str("#############\n");
+        burrow.push_str("#A.........A#\n");
+        burrow.push_str("###.#C#B#D###\n");
+        burrow.push_str("  #.#D#C#B#\n");
+        burrow.push_str("  #########\n");
+        let burrow = Burrow::from_str(&burrow).unwrap();
+        assert_eq!(moves_from(&burrow, 1, 1), [((3, 3), Energy::Finite(4))]);
+        assert_eq!(moves_from(&burrow, 11, 1), [((3, 3), Energy::Finite(10))]);
+    }
 }
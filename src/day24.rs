@@ -0,0 +1,412 @@
+use anyhow::{anyhow, Error, Result};
+use std::path::Path;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    W,
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Register(Register),
+    Literal(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Inp(Register),
+    Add(Register, Operand),
+    Mul(Register, Operand),
+    Div(Register, Operand),
+    Mod(Register, Operand),
+    Eql(Register, Operand),
+}
+
+impl Register {
+    fn index(self) -> usize {
+        match self {
+            Self::W => 0,
+            Self::X => 1,
+            Self::Y => 2,
+            Self::Z => 3,
+        }
+    }
+}
+
+impl FromStr for Register {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "w" => Ok(Self::W),
+            "x" => Ok(Self::X),
+            "y" => Ok(Self::Y),
+            "z" => Ok(Self::Z),
+            r => Err(anyhow!("Unknown register {:?}", r)),
+        }
+    }
+}
+
+impl FromStr for Operand {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<Register>() {
+            Ok(r) => Ok(Self::Register(r)),
+            Err(_) => Ok(Self::Literal(s.parse().map_err(|_| {
+                anyhow!("{:?} is neither a register nor a number", s)
+            })?)),
+        }
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let op = parts.next().ok_or_else(|| anyhow!("Empty instruction"))?;
+        let a: Register = parts
+            .next()
+            .ok_or_else(|| anyhow!("Missing register for {:?}", op))?
+            .parse()?;
+        if op == "inp" {
+            return Ok(Self::Inp(a));
+        }
+
+        let b: Operand = parts
+            .next()
+            .ok_or_else(|| anyhow!("Missing operand for {:?}", op))?
+            .parse()?;
+        Ok(match op {
+            "add" => Self::Add(a, b),
+            "mul" => Self::Mul(a, b),
+            "div" => Self::Div(a, b),
+            "mod" => Self::Mod(a, b),
+            "eql" => Self::Eql(a, b),
+            o => return Err(anyhow!("Unknown instruction {:?}", o)),
+        })
+    }
+}
+
+/// Run the program with the given inputs and return the final state of the registers
+pub fn run(program: &[Instruction], inputs: &[i64]) -> Result<[i64; 4]> {
+    execute(program, inputs, |_, _| ())
+}
+
+/// Run the program with the given inputs and return every instruction together with the state of
+/// the registers right after it was executed
+pub fn trace(program: &[Instruction], inputs: &[i64]) -> Result<Vec<(Instruction, [i64; 4])>> {
+    let mut steps = Vec::with_capacity(program.len());
+    execute(program, inputs, |instruction, registers| {
+        steps.push((*instruction, *registers))
+    })?;
+    Ok(steps)
+}
+
+/// Run the program, calling `on_step` with the registers after every instruction
+fn execute(
+    program: &[Instruction],
+    inputs: &[i64],
+    mut on_step: impl FnMut(&Instruction, &[i64; 4]),
+) -> Result<[i64; 4]> {
+    let mut registers = [0i64; 4];
+    let mut inputs = inputs.iter().copied();
+
+    for instruction in program {
+        let value = |b: Operand| match b {
+            Operand::Register(r) => registers[r.index()],
+            Operand::Literal(v) => v,
+        };
+        let overflow = || {
+            anyhow!(
+                "{:?} overflowed with registers {:?}",
+                instruction,
+                registers
+            )
+        };
+        let (a, v) = match *instruction {
+            Instruction::Inp(a) => (
+                a,
+                inputs
+                    .next()
+                    .ok_or_else(|| anyhow!("Program ran out of inputs"))?,
+            ),
+            Instruction::Add(a, b) => (
+                a,
+                registers[a.index()]
+                    .checked_add(value(b))
+                    .ok_or_else(overflow)?,
+            ),
+            Instruction::Mul(a, b) => (
+                a,
+                registers[a.index()]
+                    .checked_mul(value(b))
+                    .ok_or_else(overflow)?,
+            ),
+            Instruction::Div(a, b) => match value(b) {
+                0 => return Err(anyhow!("Division by zero")),
+                b => (a, registers[a.index()].checked_div(b).ok_or_else(overflow)?),
+            },
+            Instruction::Mod(a, b) => match (registers[a.index()], value(b)) {
+                (a_value, b) if a_value < 0 || b <= 0 => {
+                    return Err(anyhow!("Invalid modulo {} % {}", a_value, b))
+                }
+                (a_value, b) => (a, a_value % b),
+            },
+            Instruction::Eql(a, b) => (a, (registers[a.index()] == value(b)) as i64),
+        };
+        registers[a.index()] = v;
+        on_step(instruction, &registers);
+    }
+    Ok(registers)
+}
+
+/// Extract how the digits of the model number relate to each other
+///
+/// The MONAD program consists of 14 blocks, one per digit, that either push the digit plus an
+/// offset onto a base 26 stack in `z`, or try to pop a previously pushed digit. A pop only succeeds
+/// if the digit equals the popped value plus a block specific offset, and the model number is only
+/// valid if every pop succeeds. This returns `(push_index, pop_index, offset)` for every pair of
+/// blocks, meaning that `digit[pop_index] == digit[push_index] + offset`.
+pub fn extract_constraints(program: &[Instruction]) -> Result<Vec<(usize, usize, i64)>> {
+    let mut block_starts = program
+        .iter()
+        .enumerate()
+        .filter(|(_, i)| matches!(i, Instruction::Inp(_)))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if block_starts.first() != Some(&0) {
+        return Err(anyhow!("Program must start with an inp instruction"));
+    }
+    block_starts.push(program.len());
+
+    let literal = |block: &[Instruction], i: usize| match block.get(i) {
+        Some(
+            Instruction::Div(Register::Z, Operand::Literal(v))
+            | Instruction::Add(Register::X, Operand::Literal(v))
+            | Instruction::Add(Register::Y, Operand::Literal(v)),
+        ) => Ok(*v),
+        _ => Err(anyhow!("Unexpected instruction at offset {} of block", i)),
+    };
+
+    let mut stack = Vec::new();
+    let mut constraints = Vec::new();
+    for (digit, range) in block_starts.windows(2).enumerate() {
+        let block = &program[range[0]..range[1]];
+        if block.len() != 18 {
+            return Err(anyhow!("Block {} doesn't have 18 instructions", digit));
+        }
+
+        match literal(block, 4)? {
+            1 => stack.push((digit, literal(block, 15)?)),
+            26 => {
+                let (push_digit, push_offset) = stack
+                    .pop()
+                    .ok_or_else(|| anyhow!("Block {} pops from an empty stack", digit))?;
+                constraints.push((push_digit, digit, push_offset + literal(block, 5)?));
+            }
+            d => return Err(anyhow!("Unexpected divisor {} in block {}", d, digit)),
+        }
+    }
+
+    if !stack.is_empty() {
+        return Err(anyhow!("Not every pushed digit is popped"));
+    }
+    Ok(constraints)
+}
+
+/// Build a model number from the constraints by picking each pair of digits using the given
+/// function, which receives the offset and must return the digits for the push and pop index
+fn model_number(
+    constraints: &[(usize, usize, i64)],
+    pick: impl Fn(i64) -> (i64, i64),
+) -> Result<usize> {
+    let mut digits = vec![0; 2 * constraints.len()];
+    for &(push, pop, offset) in constraints {
+        if !(-8..=8).contains(&offset) {
+            return Err(anyhow!("No digits satisfy an offset of {}", offset));
+        }
+        let (push_digit, pop_digit) = pick(offset);
+        digits[push] = push_digit;
+        digits[pop] = pop_digit;
+    }
+    Ok(digits.into_iter().fold(0, |n, d| 10 * n + d as usize))
+}
+
+fn part_a(constraints: &[(usize, usize, i64)]) -> Result<usize> {
+    model_number(constraints, |offset| {
+        if offset >= 0 {
+            (9 - offset, 9)
+        } else {
+            (9, 9 + offset)
+        }
+    })
+}
+
+fn part_b(constraints: &[(usize, usize, i64)]) -> Result<usize> {
+    model_number(constraints, |offset| {
+        if offset >= 0 {
+            (1, 1 + offset)
+        } else {
+            (1 - offset, 1)
+        }
+    })
+}
+
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let program = input
+        .lines()
+        .map(|l| l.parse::<Instruction>())
+        .collect::<Result<Vec<_>>>()?;
+    let constraints = extract_constraints(&program)?;
+    Ok((part_a(&constraints)?, Some(part_b(&constraints)?)))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generate a program with the same structure as the MONAD using the given divisor and
+    /// offsets for each block
+    fn monad(blocks: &[(i64, i64, i64)]) -> Result<Vec<Instruction>> {
+        let mut source = Vec::new();
+        for (div, check, offset) in blocks {
+            source.extend([
+                "inp w".to_string(),
+                "mul x 0".to_string(),
+                "add x z".to_string(),
+                "mod x 26".to_string(),
+                format!("div z {}", div),
+                format!("add x {}", check),
+                "eql x w".to_string(),
+                "eql x 0".to_string(),
+                "mul y 0".to_string(),
+                "add y 25".to_string(),
+                "mul y x".to_string(),
+                "add y 1".to_string(),
+                "mul z y".to_string(),
+                "mul y 0".to_string(),
+                "add y w".to_string(),
+                format!("add y {}", offset),
+                "mul y x".to_string(),
+                "add z y".to_string(),
+            ]);
+        }
+        source.iter().map(|l| l.parse()).collect()
+    }
+
+    fn digits(n: usize) -> Vec<i64> {
+        n.to_string()
+            .chars()
+            .map(|c| c.to_digit(10).unwrap() as i64)
+            .collect()
+    }
+
+    #[test]
+    fn test_run() -> Result<()> {
+        let negate = ["inp x", "mul x -1"]
+            .iter()
+            .map(|l| l.parse())
+            .collect::<Result<Vec<Instruction>>>()?;
+        assert_eq!(run(&negate, &[7])?, [0, -7, 0, 0]);
+
+        let is_triple = ["inp z", "inp x", "mul z 3", "eql z x"]
+            .iter()
+            .map(|l| l.parse())
+            .collect::<Result<Vec<Instruction>>>()?;
+        assert_eq!(run(&is_triple, &[3, 9])?[3], 1);
+        assert_eq!(run(&is_triple, &[3, 8])?[3], 0);
+        assert!(run(&is_triple, &[3]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_instructions() -> Result<()> {
+        let program = |source: &[&str]| {
+            source
+                .iter()
+                .map(|l| l.parse())
+                .collect::<Result<Vec<Instruction>>>()
+        };
+        assert!(run(&program(&["inp x", "div x 0"])?, &[1]).is_err());
+        assert!(run(&program(&["inp x", "mod x 0"])?, &[1]).is_err());
+        assert!(run(&program(&["inp x", "mod x -2"])?, &[1]).is_err());
+        assert!(run(&program(&["inp x", "mod x 2"])?, &[-1]).is_err());
+
+        // Overflowing any operation is an error rather than a panic
+        assert!(run(&program(&["inp x", "add x 1"])?, &[i64::MAX]).is_err());
+        assert!(run(&program(&["inp x", "mul x 2"])?, &[i64::MAX]).is_err());
+        assert!(run(&program(&["inp x", "div x -1"])?, &[i64::MIN]).is_err());
+        assert_eq!(run(&program(&["inp x", "div x -1"])?, &[7])?, [0, -7, 0, 0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_trace() -> Result<()> {
+        let negate = ["inp x", "mul x -1"]
+            .iter()
+            .map(|l| l.parse())
+            .collect::<Result<Vec<Instruction>>>()?;
+        let steps = trace(&negate, &[7])?;
+        assert_eq!(
+            steps,
+            [(negate[0], [0, 7, 0, 0]), (negate[1], [0, -7, 0, 0])]
+        );
+        assert_eq!(steps.last().map(|(_, r)| r[Register::X.index()]), Some(-7));
+        assert!(trace(&negate, &[]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_constraints() -> Result<()> {
+        let program = monad(&[
+            (1, 11, 6),
+            (1, 13, 14),
+            (1, 15, 14),
+            (26, -8, 10),
+            (1, 13, 9),
+            (1, 15, 12),
+            (26, -11, 8),
+            (26, -4, 13),
+            (26, -15, 12),
+            (1, 14, 6),
+            (1, 14, 9),
+            (26, -1, 15),
+            (26, -8, 4),
+            (26, -14, 10),
+        ])?;
+        let constraints = extract_constraints(&program)?;
+        assert_eq!(
+            constraints,
+            vec![
+                (2, 3, 6),
+                (5, 6, 1),
+                (4, 7, 5),
+                (1, 8, -1),
+                (10, 11, 8),
+                (9, 12, -2),
+                (0, 13, -8)
+            ]
+        );
+
+        let largest = part_a(&constraints)?;
+        let smallest = part_b(&constraints)?;
+        assert_eq!(largest, 99_394_899_891_971);
+        assert_eq!(smallest, 92_171_126_131_911);
+
+        // Both model numbers must be accepted by the program itself, while a slight change is not
+        assert_eq!(run(&program, &digits(largest))?[3], 0);
+        assert_eq!(run(&program, &digits(smallest))?[3], 0);
+        assert_ne!(run(&program, &digits(largest - 10))?[3], 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Empty,
+    East,
+    South,
+}
+
+/// Move every sea cucumber of the given herd one step if the cell in front of it is empty.
+/// Returns true if any sea cucumber moved.
+fn move_herd(grid: &mut [Vec<Cell>], herd: Cell) -> bool {
+    let height = grid.len();
+    let width = grid[0].len();
+    let (dx, dy) = match herd {
+        Cell::East => (1, 0),
+        Cell::South => (0, 1),
+        Cell::Empty => return false,
+    };
+
+    // All sea cucumbers in the herd consider the current state before any of them move
+    let moves = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| grid[y][x] == herd)
+        .map(|(x, y)| ((x, y), ((x + dx) % width, (y + dy) % height)))
+        .filter(|&(_, (nx, ny))| grid[ny][nx] == Cell::Empty)
+        .collect::<Vec<_>>();
+
+    for &((x, y), (nx, ny)) in &moves {
+        grid[y][x] = Cell::Empty;
+        grid[ny][nx] = herd;
+    }
+    !moves.is_empty()
+}
+
+fn part_a(mut grid: Vec<Vec<Cell>>) -> usize {
+    let mut num_steps = 0;
+    loop {
+        num_steps += 1;
+        let east_moved = move_herd(&mut grid, Cell::East);
+        let south_moved = move_herd(&mut grid, Cell::South);
+        if !east_moved && !south_moved {
+            break num_steps;
+        }
+    }
+}
+
+fn parse_grid(input: &str) -> Result<Vec<Vec<Cell>>> {
+    let grid = input
+        .lines()
+        .map(|l| {
+            l.chars()
+                .map(|c| match c {
+                    '.' => Ok(Cell::Empty),
+                    '>' => Ok(Cell::East),
+                    'v' => Ok(Cell::South),
+                    c => Err(anyhow!("Invalid character {:?}", c)),
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let width = grid.first().map(Vec::len).unwrap_or(0);
+    if width == 0 || grid.iter().any(|row| row.len() != width) {
+        return Err(anyhow!("The sea floor must be a non-empty rectangle"));
+    }
+    Ok(grid)
+}
+
+/// There is no puzzle for part B on the last day, so we just return a greeting instead
+pub fn solve(input: &str) -> Result<(usize, Option<String>)> {
+    let grid = parse_grid(input)?;
+    Ok((part_a(grid), Some("Merry Christmas!".to_string())))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<String>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+v...>>.vv>
+.vv>>.vv..
+>>.>v>...v
+>>v>>.>.v.
+v>v.vv.v..
+>.>>..v...
+.vv..>.>v.
+v.v..>>v.v
+....v..v.>
+";
+
+    #[test]
+    fn test_move_herd() -> Result<()> {
+        let mut grid = parse_grid("...>>>>>...")?;
+        assert!(move_herd(&mut grid, Cell::East));
+        assert_eq!(grid, parse_grid("...>>>>.>..")?);
+        assert!(!move_herd(&mut grid, Cell::South));
+
+        // Sea cucumbers that move off the edge wrap around to the other side
+        let mut grid = parse_grid("..>\nv..\n...")?;
+        move_herd(&mut grid, Cell::East);
+        move_herd(&mut grid, Cell::South);
+        assert_eq!(grid, parse_grid(">..\n...\nv..")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_example() -> Result<()> {
+        assert_eq!(solve(EXAMPLE)?, (58, Some("Merry Christmas!".to_string())));
+        assert!(parse_grid("..\n.").is_err());
+        Ok(())
+    }
+}
@@ -1,12 +1,13 @@
 use anyhow::{anyhow, Result};
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::path::Path;
 
-fn part_a<R: AsRef<str>>(report: &[R]) -> Result<usize> {
+/// Calculate the power consumption in a single pass, without requiring the whole report in memory
+pub fn part_a_iter<R: AsRef<str>>(report: impl Iterator<Item = R>) -> Result<usize> {
     let mut ones = Vec::new();
+    let mut num_lines = 0;
     for line in report {
+        num_lines += 1;
         let line = line.as_ref();
         ones.resize(line.len(), 0);
         for (i, c) in line.chars().rev().enumerate() {
@@ -21,7 +22,7 @@ fn part_a<R: AsRef<str>>(report: &[R]) -> Result<usize> {
     let mut gamma = 0;
     let mut epsilon = 0;
     for (i, num_ones) in ones.into_iter().enumerate() {
-        if num_ones > report.len() / 2 {
+        if num_ones > num_lines / 2 {
             gamma |= 1 << i;
         } else {
             epsilon |= 1 << i;
@@ -31,6 +32,10 @@ fn part_a<R: AsRef<str>>(report: &[R]) -> Result<usize> {
     Ok(gamma * epsilon)
 }
 
+fn part_a<R: AsRef<str>>(report: &[R]) -> Result<usize> {
+    part_a_iter(report.iter())
+}
+
 fn count_ones<R: AsRef<str>>(report: impl Iterator<Item = R>) -> Result<Vec<usize>> {
     let mut iter = report.peekable();
 
@@ -39,8 +44,7 @@ fn count_ones<R: AsRef<str>>(report: impl Iterator<Item = R>) -> Result<Vec<usiz
         None => return Ok(Vec::new()),
     };
 
-    let mut ones = Vec::new();
-    ones.resize(num_digits, 0usize);
+    let mut ones = vec![0usize; num_digits];
 
     for line in iter {
         let line = line.as_ref();
@@ -56,67 +60,150 @@ fn count_ones<R: AsRef<str>>(report: impl Iterator<Item = R>) -> Result<Vec<usiz
     Ok(ones)
 }
 
-fn part_b<R: AsRef<str>>(report: &[R]) -> Result<usize> {
-    let mut oxygen_generators: HashSet<_> = report.iter().map(AsRef::as_ref).collect();
-    let mut co2_scrubbers: HashSet<_> = oxygen_generators.clone();
+/// Repeatedly filter the candidates by the most common bit in each position, or the least common
+/// one if `keep_most_common` is false, until there is only one left. Returns the candidates that
+/// survived each bit position.
+fn filter_candidates(
+    mut candidates: HashSet<&str>,
+    keep_most_common: bool,
+) -> Result<Vec<HashSet<&str>>> {
+    let mut steps = Vec::new();
+    for i in 0.. {
+        if candidates.len() <= 1 {
+            break;
+        }
 
-    let mut i = 0;
-    while oxygen_generators.len() > 1 {
-        let ones = count_ones(oxygen_generators.iter())?;
-        let most_common = if ones[i] >= oxygen_generators.len() - ones[i] {
-            '1'
-        } else {
-            '0'
-        };
-        oxygen_generators.retain(|line| line.chars().nth(i).unwrap() == most_common);
-        i += 1;
-    }
+        let ones = count_ones(candidates.iter())?;
+        let num_ones = *ones
+            .get(i)
+            .ok_or_else(|| anyhow!("Unable to narrow the report down to a single number"))?;
 
-    let mut i = 0;
-    while co2_scrubbers.len() > 1 {
-        let ones = count_ones(co2_scrubbers.iter())?;
-        let most_common = if ones[i] >= co2_scrubbers.len() - ones[i] {
+        // When all candidates share the same bit there is nothing to filter on. Without this the
+        // least common bit would be one that no candidate has, which would remove all of them.
+        if num_ones == 0 || num_ones == candidates.len() {
+            steps.push(candidates.clone());
+            continue;
+        }
+
+        let most_common = if num_ones >= candidates.len() - num_ones {
             '1'
         } else {
             '0'
         };
-        co2_scrubbers.retain(|line| line.chars().nth(i).unwrap() != most_common);
-        i += 1;
+        candidates.retain(|line| (line.chars().nth(i) == Some(most_common)) == keep_most_common);
+        steps.push(candidates.clone());
     }
+    Ok(steps)
+}
 
-    let oxygen_generator_rating =
-        usize::from_str_radix(oxygen_generators.into_iter().next().unwrap(), 2)?;
-    let co2_scrubber_rating = usize::from_str_radix(co2_scrubbers.into_iter().next().unwrap(), 2)?;
+fn rating(candidates: HashSet<&str>, keep_most_common: bool) -> Result<usize> {
+    let last = match filter_candidates(candidates.clone(), keep_most_common)?.pop() {
+        Some(last) => last.into_iter().next(),
+        None => candidates.into_iter().next(),
+    };
+    let last = last.ok_or_else(|| anyhow!("The report is empty"))?;
+    Ok(usize::from_str_radix(last, 2)?)
+}
 
+/// Return the candidates that survive each bit position for the oxygen generator and CO2
+/// scrubber ratings. Each set of candidates is sorted and separated by commas.
+pub fn life_support_trace<R: AsRef<str>>(report: &[R]) -> Result<(Vec<String>, Vec<String>)> {
+    let candidates: HashSet<_> = report.iter().map(AsRef::as_ref).collect();
+    let trace = |keep_most_common| -> Result<Vec<String>> {
+        let steps = filter_candidates(candidates.clone(), keep_most_common)?;
+        Ok(steps
+            .into_iter()
+            .map(|step| {
+                let mut step = step.into_iter().collect::<Vec<_>>();
+                step.sort_unstable();
+                step.join(",")
+            })
+            .collect())
+    };
+    Ok((trace(true)?, trace(false)?))
+}
+
+fn part_b<R: AsRef<str>>(report: &[R]) -> Result<usize> {
+    let candidates: HashSet<_> = report.iter().map(AsRef::as_ref).collect();
+    let oxygen_generator_rating = rating(candidates.clone(), true)?;
+    let co2_scrubber_rating = rating(candidates, false)?;
     Ok(oxygen_generator_rating * co2_scrubber_rating)
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let file = File::open(path)?;
-    let report = io::BufReader::new(file)
-        .lines()
-        .collect::<io::Result<Vec<String>>>()?;
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let report = input.lines().collect::<Vec<_>>();
     Ok((part_a(&report)?, Some(part_b(&report)?)))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const REPORT: &'static [&str] = &[
+    const REPORT: &[&str] = &[
         "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000", "11001",
         "00010", "01010",
     ];
 
     #[test]
     fn test_part_a() -> Result<()> {
-        assert_eq!(part_a(&REPORT)?, 198);
+        assert_eq!(part_a(REPORT)?, 198);
+        Ok(())
+    }
+
+    #[test]
+    fn test_part_a_iter() -> Result<()> {
+        // Lines are produced lazily, one owned string at a time
+        let joined = REPORT.join("\n");
+        let lines = joined.split('\n').map(str::to_string);
+        assert_eq!(part_a_iter(lines)?, 198);
+        assert!(part_a_iter(["0120"].iter()).is_err());
         Ok(())
     }
 
     #[test]
     fn test_part_b() -> Result<()> {
-        assert_eq!(part_b(&REPORT)?, 230);
+        assert_eq!(part_b(REPORT)?, 230);
+        Ok(())
+    }
+
+    #[test]
+    fn test_constant_column() -> Result<()> {
+        // The first bit is the same for every number, so it can't be used to filter on
+        let report = ["10110", "11001", "10011", "11110"];
+        let candidates = report.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(rating(candidates.clone(), true)?, 0b11110);
+        assert_eq!(rating(candidates, false)?, 0b10011);
+        assert_eq!(part_b(&report)?, 0b11110 * 0b10011);
+
+        // Once the first bit has been filtered on, the two middle bits are the same for everyone
+        let report = ["1010", "1011", "0010", "0011"];
+        assert_eq!(part_b(&report)?, 0b1011 * 0b0010);
+
+        assert!(part_b::<&str>(&[]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_life_support_trace() -> Result<()> {
+        let (oxygen, co2) = life_support_trace(REPORT)?;
+
+        // Oxygen keeps 7, 4, 3, 2 and finally 1 number, just like the walkthrough
+        let sizes = oxygen
+            .iter()
+            .map(|step| step.split(',').count())
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, [7, 4, 3, 2, 1]);
+        assert_eq!(oxygen[1], "10000,10101,10110,10111");
+        assert_eq!(oxygen.last().map(String::as_str), Some("10111"));
+
+        assert_eq!(
+            co2,
+            ["00010,00100,00111,01010,01111", "01010,01111", "01010"]
+        );
         Ok(())
     }
 }
@@ -0,0 +1,213 @@
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+pub type Board = Vec<Vec<usize>>;
+
+/// A board where every number that has been drawn is replaced by `None`
+pub type BoardState = Vec<Vec<Option<usize>>>;
+
+fn has_won(state: &BoardState) -> bool {
+    let full_row = state.iter().any(|row| row.iter().all(Option::is_none));
+    let full_column =
+        (0..state[0].len()).any(|x| state.iter().all(|row| row.get(x) == Some(&None)));
+    full_row || full_column
+}
+
+fn unmarked_sum(state: &BoardState) -> usize {
+    state.iter().flatten().flatten().sum()
+}
+
+/// Play bingo until we run out of numbers. Returns every drawn number together with the final
+/// state of each board that won on it, indexed by board. Boards that didn't win on the draw are
+/// `None`.
+fn play(boards: &[Board], draws: &[usize]) -> Vec<(usize, Vec<Option<BoardState>>)> {
+    let mut states = boards
+        .iter()
+        .map(|b| {
+            let state = b.iter().map(|row| row.iter().copied().map(Some).collect());
+            Some(state.collect::<BoardState>())
+        })
+        .collect::<Vec<_>>();
+
+    let mut rounds = Vec::with_capacity(draws.len());
+    for &draw in draws {
+        let mut winners = vec![None; boards.len()];
+        for (slot, winner) in states.iter_mut().zip(winners.iter_mut()) {
+            let state = match slot {
+                Some(state) => state,
+                None => continue,
+            };
+            state
+                .iter_mut()
+                .flatten()
+                .filter(|n| **n == Some(draw))
+                .for_each(|n| *n = None);
+            if has_won(state) {
+                *winner = slot.take();
+            }
+        }
+        rounds.push((draw, winners));
+    }
+    rounds
+}
+
+/// Return every drawn number together with the score of each board that won on it, indexed by
+/// board. Boards that didn't win on the draw, including those that had already won, are `None`.
+pub fn score_progression(boards: &[Board], draws: &[usize]) -> Vec<(usize, Vec<Option<usize>>)> {
+    play(boards, draws)
+        .into_iter()
+        .map(|(draw, winners)| {
+            let scores = winners
+                .into_iter()
+                .map(|state| state.map(|s| draw * unmarked_sum(&s)))
+                .collect();
+            (draw, scores)
+        })
+        .collect()
+}
+
+/// All scores in the order the boards won
+fn scores(boards: &[Board], draws: &[usize]) -> impl Iterator<Item = usize> {
+    score_progression(boards, draws)
+        .into_iter()
+        .flat_map(|(_, scores)| scores.into_iter().flatten())
+}
+
+/// Return the state of the first board to win, at the moment it won
+pub fn winning_board_state(boards: &[Board], draws: &[usize]) -> Option<BoardState> {
+    play(boards, draws)
+        .into_iter()
+        .flat_map(|(_, winners)| winners.into_iter().flatten())
+        .next()
+}
+
+fn part_a(boards: &[Board], draws: &[usize]) -> Option<usize> {
+    scores(boards, draws).next()
+}
+
+fn part_b(boards: &[Board], draws: &[usize]) -> Option<usize> {
+    scores(boards, draws).last()
+}
+
+fn parse_input(input: &str) -> Result<(Vec<Board>, Vec<usize>)> {
+    let (draws_str, boards_str) = input
+        .split_once("\n\n")
+        .ok_or_else(|| anyhow!("Unable to find any boards"))?;
+
+    let draws = draws_str
+        .trim()
+        .split(',')
+        .map(|d| Ok(d.parse()?))
+        .collect::<Result<Vec<usize>>>()?;
+
+    let boards = boards_str
+        .split("\n\n")
+        .map(|b| {
+            let board = b
+                .lines()
+                .map(|l| {
+                    l.split_whitespace()
+                        .map(|n| Ok(n.parse()?))
+                        .collect::<Result<Vec<usize>>>()
+                })
+                .collect::<Result<Board>>()?;
+
+            let width = board.first().map(Vec::len).unwrap_or(0);
+            if width == 0 || board.iter().any(|row| row.len() != width) {
+                return Err(anyhow!("Boards must be non-empty rectangles"));
+            }
+            Ok(board)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((boards, draws))
+}
+
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let (boards, draws) = parse_input(input)?;
+    Ok((
+        part_a(&boards, &draws).ok_or_else(|| anyhow!("No board ever wins"))?,
+        part_b(&boards, &draws),
+    ))
+}
+
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7
+";
+
+    #[test]
+    fn test_example() -> Result<()> {
+        assert_eq!(solve(EXAMPLE)?, (4512, Some(1924)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_winning_board_state() -> Result<()> {
+        let (boards, draws) = parse_input(EXAMPLE)?;
+        let state = winning_board_state(&boards, &draws).unwrap();
+
+        // The third board wins on 24 by completing its top row
+        assert_eq!(state[0], vec![None; 5]);
+        assert_eq!(state[1], vec![Some(10), Some(16), Some(15), None, Some(19)]);
+        assert_eq!(unmarked_sum(&state), 188);
+
+        // The score of the first win is based on the same state
+        let first_win = score_progression(&boards, &draws)
+            .into_iter()
+            .find_map(|(draw, scores)| Some((draw, scores.into_iter().flatten().next()?)));
+        assert_eq!(first_win, Some((24, 24 * unmarked_sum(&state))));
+
+        assert_eq!(winning_board_state(&boards, &draws[..5]), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_score_progression() -> Result<()> {
+        let (boards, draws) = parse_input(EXAMPLE)?;
+        let progression = score_progression(&boards, &draws);
+        assert_eq!(progression.len(), draws.len());
+        assert!(progression.iter().all(|(_, scores)| scores.len() == 3));
+
+        // Only the third board wins on 24
+        let (draw, scores) = progression
+            .iter()
+            .find(|(_, scores)| scores.contains(&Some(4512)))
+            .unwrap();
+        assert_eq!(*draw, 24);
+        assert_eq!(scores, &[None, None, Some(4512)]);
+
+        // Every board wins exactly once
+        let wins = progression
+            .iter()
+            .flat_map(|(_, scores)| scores.iter().flatten())
+            .count();
+        assert_eq!(wins, 3);
+        Ok(())
+    }
+}
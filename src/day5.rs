@@ -1,23 +1,39 @@
+use crate::sparse_map::SparseMap;
 use anyhow::{anyhow, Result};
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::path::Path;
 use std::str::FromStr;
 
+/// The number of vents covering each point. Uses the faster, non-cryptographic Fx hash when the
+/// `rustc-hash` feature is enabled
+#[cfg(feature = "rustc-hash")]
+type OverlapMap = rustc_hash::FxHashMap<(isize, isize), usize>;
+#[cfg(not(feature = "rustc-hash"))]
+type OverlapMap = std::collections::HashMap<(isize, isize), usize>;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vent {
-    start: (isize, isize),
-    end: (isize, isize),
+    pub start: (isize, isize),
+    pub end: (isize, isize),
 }
 
 impl Vent {
+    /// Iterate over every point covered by the vent, including both ends
     fn iter_coords(&self) -> impl Iterator<Item = (isize, isize)> + '_ {
         let dx = (self.end.0 - self.start.0).signum();
         let dy = (self.end.1 - self.start.1).signum();
-        (0..)
-            .map(move |i| (self.start.0 + dx * i, self.start.1 + dy * i))
-            .take_while(move |&(x, y)| (x, y) != (self.end.0 + dx, self.end.1 + dy))
+        (0..self.len() as isize).map(move |i| (self.start.0 + dx * i, self.start.1 + dy * i))
+    }
+
+    /// The number of points covered by the vent
+    fn len(&self) -> usize {
+        let dx = self.end.0.abs_diff(self.start.0);
+        let dy = self.end.1.abs_diff(self.start.1);
+        dx.max(dy) + 1
+    }
+
+    fn is_diagonal(&self) -> bool {
+        self.start.0 != self.end.0 && self.start.1 != self.end.1
     }
 }
 
@@ -26,7 +42,7 @@ impl FromStr for Vent {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let (start, end) = s
-            .split_once(" -> ")
+            .split_once("->")
             .ok_or_else(|| anyhow!("No delimiter found for vent"))?;
 
         let (start_x, start_y) = start
@@ -36,50 +52,89 @@ impl FromStr for Vent {
             .split_once(',')
             .ok_or_else(|| anyhow!("Invalid vent end"))?;
 
+        // Allow whitespace around the numbers
         Ok(Vent {
-            start: (start_x.parse()?, start_y.parse()?),
-            end: (end_x.parse()?, end_y.parse()?),
+            start: (start_x.trim().parse()?, start_y.trim().parse()?),
+            end: (end_x.trim().parse()?, end_y.trim().parse()?),
         })
     }
 }
 
-pub fn part_a(vents: &[Vent]) -> usize {
-    let mut map: HashMap<(isize, isize), usize> = HashMap::new();
+/// Count the number of vents covering each point. The map is sized up front from the total length
+/// of the vents, which is an upper bound on the number of points, to avoid rehashing
+fn overlap_map(vents: &[Vent], include_diagonals: bool) -> OverlapMap {
+    let vents = vents
+        .iter()
+        .filter(|v| include_diagonals || !v.is_diagonal());
+    let capacity = vents.clone().map(Vent::len).sum();
+    let mut map = OverlapMap::with_capacity_and_hasher(capacity, Default::default());
     for v in vents {
-        if v.start.0 != v.end.0 && v.start.1 != v.end.1 {
-            continue;
-        }
         for (x, y) in v.iter_coords() {
             *map.entry((x, y)).or_default() += 1;
         }
     }
-    map.into_values().filter(|count| *count >= 2).count()
+    map
 }
 
-pub fn part_b(vents: &[Vent]) -> usize {
-    let mut map: HashMap<(isize, isize), usize> = HashMap::new();
-    for v in vents {
-        for (x, y) in v.iter_coords() {
-            *map.entry((x, y)).or_default() += 1;
+/// Count the points where at least two vents overlap
+fn count_overlaps(vents: &[Vent], include_diagonals: bool) -> usize {
+    overlap_map(vents, include_diagonals)
+        .into_values()
+        .filter(|count| *count >= 2)
+        .count()
+}
+
+/// Draw the number of vents covering each point within the bounding box of the vents. Points
+/// without vents are drawn as `.` and points with 10 or more as `#`. Nothing is drawn if there are
+/// no vents.
+pub fn render_overlaps(vents: &[Vent], include_diagonals: bool) -> String {
+    let map = overlap_map(vents, include_diagonals)
+        .into_iter()
+        .collect::<SparseMap<_>>();
+    let ((min_x, min_y), (max_x, max_y)) = match map.bounds() {
+        Some(bounds) => bounds,
+        None => return String::new(),
+    };
+
+    let mut output = String::new();
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            output.push(match map.get((x, y)).copied().unwrap_or(0) {
+                0 => '.',
+                count @ 1..=9 => char::from_digit(count as u32, 10).unwrap(),
+                _ => '#',
+            });
         }
+        output.push('\n');
     }
-    map.into_values().filter(|count| *count >= 2).count()
+    output
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let file = File::open(path)?;
-    let vents = io::BufReader::new(file)
+pub fn part_a(vents: &[Vent]) -> usize {
+    count_overlaps(vents, false)
+}
+
+pub fn part_b(vents: &[Vent]) -> usize {
+    count_overlaps(vents, true)
+}
+
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let vents = input
         .lines()
-        .map(|lr| lr?.parse::<Vent>())
+        .map(|l| l.parse::<Vent>())
         .collect::<Result<Vec<Vent>>>()?;
     Ok((part_a(&vents), Some(part_b(&vents))))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const VENTS: &'static [&str] = &[
+    const VENTS: &[&str] = &[
         "0,9 -> 5,9",
         "8,0 -> 0,8",
         "9,4 -> 3,4",
@@ -101,6 +156,15 @@ mod tests {
                 end: (3, 4)
             }
         );
+        assert_eq!(
+            "0, 9 -> 5, 9".parse::<Vent>()?,
+            "0,9 -> 5,9".parse::<Vent>()?
+        );
+        assert_eq!(
+            " 0 ,9->5,  9 ".parse::<Vent>()?,
+            "0,9 -> 5,9".parse::<Vent>()?
+        );
+        assert!("0 9 -> 5 9".parse::<Vent>().is_err());
         Ok(())
     }
 
@@ -123,4 +187,78 @@ mod tests {
         assert_eq!(part_b(&vents), 12);
         Ok(())
     }
+
+    #[test]
+    fn test_len() -> Result<()> {
+        let vents = VENTS
+            .iter()
+            .map(|l| l.parse())
+            .collect::<Result<Vec<Vent>, _>>()?;
+        for v in vents {
+            assert_eq!(v.len(), v.iter_coords().count());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_length() -> Result<()> {
+        let vent = "0,0 -> 0,0".parse::<Vent>()?;
+        assert_eq!(vent.iter_coords().collect::<Vec<_>>(), [(0, 0)]);
+
+        let vents = ["3,3 -> 3,3", "3,3 -> 3,3", "1,3 -> 5,3"]
+            .iter()
+            .map(|l| l.parse())
+            .collect::<Result<Vec<Vent>, _>>()?;
+        assert_eq!(part_a(&vents), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_overlaps() -> Result<()> {
+        let vents = VENTS
+            .iter()
+            .map(|l| l.parse())
+            .collect::<Result<Vec<Vent>, _>>()?;
+
+        let output = render_overlaps(&vents, false);
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                ".......1..",
+                "..1....1..",
+                "..1....1..",
+                ".......1..",
+                ".112111211",
+                "..........",
+                "..........",
+                "..........",
+                "..........",
+                "222111....",
+            ]
+        );
+        assert_eq!(lines[4].chars().nth(3), Some('2'));
+
+        let output = render_overlaps(&vents, true);
+        assert_eq!(output.lines().next(), Some("1.1....11."));
+        assert_eq!(output.matches(|c| ('2'..='9').contains(&c)).count(), 12);
+
+        let stacked = vec!["0,0 -> 1,0".parse::<Vent>()?; 10];
+        assert_eq!(render_overlaps(&stacked, false), "##\n");
+
+        // Without vents there is no bounding box to draw
+        assert_eq!(render_overlaps(&[], true), "");
+        let diagonal = ["0,0 -> 2,2".parse::<Vent>()?];
+        assert_eq!(render_overlaps(&diagonal, false), "");
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() -> Result<()> {
+        let vent = "8,0 -> 0,8".parse::<Vent>()?;
+        let json = serde_json::to_string(&vent)?;
+        assert_eq!(serde_json::from_str::<Vent>(&json)?, vent);
+        Ok(())
+    }
 }
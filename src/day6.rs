@@ -1,9 +1,11 @@
 use anyhow::{anyhow, Result};
 use std::path::Path;
 
-type State = [usize; 9];
+/// Number of lanternfish for each timer value
+pub type State = [usize; 9];
 
-pub fn simulation(mut state: State, num_iterations: usize) -> usize {
+/// Return the number of lanternfish for each timer value after the given number of days
+pub fn age_histogram(mut state: State, num_iterations: usize) -> State {
     for _ in 0..num_iterations {
         let num_births = state[0];
         for i in 1..state.len() {
@@ -12,30 +14,56 @@ pub fn simulation(mut state: State, num_iterations: usize) -> usize {
         state[6] += num_births;
         state[8] = num_births;
     }
+    state
+}
+
+pub fn simulation(state: State, num_iterations: usize) -> usize {
+    age_histogram(state, num_iterations).into_iter().sum()
+}
+
+/// Same as `simulation` but without any risk of overflowing for very long simulations
+#[cfg(feature = "num-bigint")]
+pub fn simulation_big(state: State, num_iterations: usize) -> num_bigint::BigUint {
+    let mut state = state.map(num_bigint::BigUint::from);
+    for _ in 0..num_iterations {
+        // Every fish at timer 0 moves to 8, which is the same as giving birth to a new fish and
+        // resetting the parent's timer to 6
+        state.rotate_left(1);
+        let num_births = state[8].clone();
+        state[6] += num_births;
+    }
     state.into_iter().sum()
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let input = std::fs::read_to_string(path)?;
-    let timers = input
-        .trim()
-        .split(',')
-        .map(|d| d.parse::<usize>())
-        .collect::<Result<Vec<_>, _>>()?;
-    let mut initial_state: State = Default::default();
-    for timer in timers {
-        if timer >= initial_state.len() {
-            return Err(anyhow!("Invalid timer {}", timer));
+/// Parse a comma separated list of timers into the number of lanternfish for each timer value
+pub fn parse_state(input: &str) -> Result<State> {
+    let mut state: State = Default::default();
+    for timer in input.trim().split(',') {
+        let timer = timer.trim().parse::<usize>()?;
+        if timer >= state.len() {
+            return Err(anyhow!(
+                "Invalid timer {}, it must be less than {}",
+                timer,
+                state.len()
+            ));
         }
-        initial_state[timer] += 1;
+        state[timer] += 1;
     }
+    Ok(state)
+}
 
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let initial_state = parse_state(input)?;
     Ok((
         simulation(initial_state, 80),
         Some(simulation(initial_state, 256)),
     ))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -46,4 +74,31 @@ mod tests {
         assert_eq!(simulation([0, 1, 1, 2, 1, 0, 0, 0, 0], 256), 26984457539);
         Ok(())
     }
+
+    #[test]
+    fn test_parse_state() -> Result<()> {
+        assert_eq!(parse_state("3,4,3,1,2")?, [0, 1, 1, 2, 1, 0, 0, 0, 0]);
+        assert_eq!(parse_state("8,0\n")?, [1, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(
+            parse_state("3,9").unwrap_err().to_string(),
+            "Invalid timer 9, it must be less than 9"
+        );
+        assert!(parse_state("3,x").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_age_histogram() {
+        let histogram = age_histogram([0, 1, 1, 2, 1, 0, 0, 0, 0], 18);
+        assert_eq!(histogram, [3, 5, 3, 2, 2, 1, 5, 1, 4]);
+        assert_eq!(histogram.into_iter().sum::<usize>(), 26);
+    }
+
+    #[test]
+    #[cfg(feature = "num-bigint")]
+    fn test_simulation_big() {
+        let state = [0, 1, 1, 2, 1, 0, 0, 0, 0];
+        assert_eq!(simulation_big(state, 256), simulation(state, 256).into());
+        assert!(simulation_big(state, 10000) > u128::MAX.into());
+    }
 }
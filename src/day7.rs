@@ -1,49 +1,81 @@
 use anyhow::Result;
 use std::path::Path;
 
-fn part_a(sorted_crabs: &[isize]) -> isize {
-    let first = sorted_crabs[0];
-    let last = sorted_crabs[sorted_crabs.len() - 1];
+/// Each step costs one fuel
+fn constant_cost(distance: isize) -> isize {
+    distance
+}
 
-    (first..=last)
-        .map(|target| {
-            sorted_crabs
-                .iter()
-                .map(|crab| (crab - target).abs())
-                .sum::<isize>()
-        })
-        .min()
-        .unwrap_or(0)
+/// Each step costs one more fuel than the last
+fn increasing_cost(distance: isize) -> isize {
+    (0..=distance).sum()
+}
+
+/// Return the total cost of moving all crabs to the target, given the cost for a single crab
+/// moving the given distance
+fn fuel(crabs: &[isize], target: isize, cost: impl Fn(isize) -> isize) -> isize {
+    crabs.iter().map(|crab| cost((crab - target).abs())).sum()
+}
+
+/// The fuel needed to align on the target when each step costs one fuel
+pub fn fuel_a(crabs: &[isize], target: isize) -> isize {
+    fuel(crabs, target, constant_cost)
+}
+
+/// The fuel needed to align on the target when each step costs one more fuel than the last
+pub fn fuel_b(crabs: &[isize], target: isize) -> isize {
+    fuel(crabs, target, increasing_cost)
 }
 
-fn part_b(sorted_crabs: &[isize]) -> isize {
-    let first = sorted_crabs[0];
-    let last = sorted_crabs[sorted_crabs.len() - 1];
+/// Return the total cost of moving all crabs to each position between the leftmost and
+/// rightmost crab, given the cost for a single crab moving the given distance
+fn cost_curve(crabs: &[isize], cost: impl Fn(isize) -> isize) -> Vec<(isize, isize)> {
+    let (first, last) = match (crabs.iter().min(), crabs.iter().max()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return Vec::new(),
+    };
 
     (first..=last)
-        .map(|target| {
-            sorted_crabs
-                .iter()
-                .map(|crab| (0..=(crab - target).abs()).sum::<isize>())
-                .sum::<isize>()
-        })
-        .min()
-        .unwrap_or(0)
+        .map(|target| (target, fuel(crabs, target, &cost)))
+        .collect()
 }
 
-pub fn main(path: &Path) -> Result<(isize, Option<isize>)> {
-    let input = std::fs::read_to_string(path)?;
-    let mut crabs = input
+/// The cost of aligning on every position when each step costs one fuel
+pub fn cost_curve_a(crabs: &[isize]) -> Vec<(isize, isize)> {
+    cost_curve(crabs, constant_cost)
+}
+
+/// The cost of aligning on every position when each step costs one more fuel than the last
+pub fn cost_curve_b(crabs: &[isize]) -> Vec<(isize, isize)> {
+    cost_curve(crabs, increasing_cost)
+}
+
+fn min_cost(curve: &[(isize, isize)]) -> isize {
+    curve.iter().map(|&(_, cost)| cost).min().unwrap_or(0)
+}
+
+fn part_a(crabs: &[isize]) -> isize {
+    min_cost(&cost_curve_a(crabs))
+}
+
+fn part_b(crabs: &[isize]) -> isize {
+    min_cost(&cost_curve_b(crabs))
+}
+
+pub fn solve(input: &str) -> Result<(isize, Option<isize>)> {
+    let crabs = input
         .trim()
         .split(',')
         .map(|d| d.parse::<isize>())
         .collect::<Result<Vec<_>, _>>()?;
 
-    crabs.sort_unstable();
-
     Ok((part_a(&crabs), Some(part_b(&crabs))))
 }
 
+pub fn main(path: &Path) -> Result<(isize, Option<isize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -56,4 +88,40 @@ mod tests {
         assert_eq!(part_b(&input), 168);
         Ok(())
     }
+
+    #[test]
+    fn test_unsorted() {
+        let input = vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+        assert_eq!(part_a(&input), 37);
+        assert_eq!(part_b(&input), 168);
+        assert_eq!(cost_curve_a(&input).len(), 17);
+    }
+
+    #[test]
+    fn test_cost_curve() {
+        let mut input = vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+        input.sort_unstable();
+
+        let curve = cost_curve_a(&input);
+        assert_eq!(curve.len(), 17);
+        assert_eq!(curve.iter().min_by_key(|&&(_, cost)| cost), Some(&(2, 37)));
+        assert!(curve.contains(&(1, 41)));
+        assert!(curve.contains(&(10, 71)));
+
+        let curve = cost_curve_b(&input);
+        assert_eq!(curve.iter().min_by_key(|&&(_, cost)| cost), Some(&(5, 168)));
+        assert!(curve.contains(&(2, 206)));
+
+        assert_eq!(cost_curve_a(&[]), Vec::new());
+    }
+
+    #[test]
+    fn test_fuel() {
+        let input = vec![16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+        assert_eq!(fuel_a(&input, 2), 37);
+        assert_eq!(fuel_a(&input, 10), 71);
+        assert_eq!(fuel_b(&input, 5), 168);
+        assert_eq!(fuel_b(&input, 2), 206);
+        assert_eq!(fuel_a(&[], 2), 0);
+    }
 }
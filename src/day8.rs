@@ -1,6 +1,4 @@
 use anyhow::{anyhow, Result};
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::path::Path;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -39,17 +37,66 @@ impl Segments {
     }
 }
 
+/// Count how many times 1, 4, 7 and 8 occur in the outputs of the displays in the puzzle input, in
+/// that order
+pub fn unique_digit_counts(input: &str) -> Result<[usize; 4]> {
+    Ok(count_unique_digits(&parse_displays(input)?))
+}
+
+fn count_unique_digits(displays: &[Display]) -> [usize; 4] {
+    let mut counts = [0; 4];
+    for output in displays.iter().flat_map(|d| d.output.iter()) {
+        match output.len() {
+            2 => counts[0] += 1,
+            4 => counts[1] += 1,
+            3 => counts[2] += 1,
+            7 => counts[3] += 1,
+            _ => (),
+        }
+    }
+    counts
+}
+
 fn part_a(displays: &[Display]) -> usize {
-    displays
-        .iter()
-        .flat_map(|d| d.output.iter())
-        .filter(|o| o.len() == 2 || o.len() == 3 || o.len() == 4 || o.len() == 7)
-        .count()
+    count_unique_digits(displays).into_iter().sum()
+}
+
+/// Check that there is the expected number of patterns for every segment count, so each digit is
+/// guaranteed to have a pattern
+fn validate_patterns(patterns: &[Segments]) -> Result<()> {
+    let expected = [
+        (2, 1, "1"),
+        (3, 1, "7"),
+        (4, 1, "4"),
+        (5, 3, "2, 3 or 5"),
+        (6, 3, "0, 6 or 9"),
+        (7, 1, "8"),
+    ];
+    for (len, num_expected, digits) in expected {
+        let num_found = patterns.iter().filter(|p| p.len() == len).count();
+        if num_found < num_expected {
+            return Err(anyhow!(
+                "Missing pattern for digit {} ({} segments)",
+                digits,
+                len
+            ));
+        }
+        if num_found > num_expected {
+            return Err(anyhow!("Too many patterns with {} segments", len));
+        }
+    }
+
+    if patterns.len() != 10 {
+        return Err(anyhow!("Expected 10 patterns, got {}", patterns.len()));
+    }
+    Ok(())
 }
 
 fn part_b(displays: &[Display]) -> Result<usize> {
     let mut sum = 0;
     for display in displays {
+        validate_patterns(&display.patterns)?;
+
         let patterns = display.patterns.iter().copied();
         let mut map = [Segments(0); 10];
 
@@ -63,10 +110,6 @@ fn part_b(displays: &[Display]) -> Result<usize> {
             }
         }
 
-        if map[1].len() == 0 || map[4].len() == 0 || map[7].len() == 0 || map[8].len() == 0 {
-            return Err(anyhow!("Couldn't find 1, 4, 7 and 8 in pattern"));
-        }
-
         map[3] = patterns
             .clone()
             .find(|&p| p.len() == 5 && p.contains(map[7]))
@@ -106,12 +149,10 @@ fn part_b(displays: &[Display]) -> Result<usize> {
     Ok(sum)
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
-    let file = File::open(path)?;
-    let displays = io::BufReader::new(file)
+fn parse_displays(input: &str) -> Result<Vec<Display>> {
+    input
         .lines()
-        .map(|lr| {
-            let line = lr?;
+        .map(|line| {
             let (patterns_str, output_str) = line
                 .split_once(" | ")
                 .ok_or_else(|| anyhow!("No display delimiter found"))?;
@@ -126,11 +167,18 @@ pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
                     .collect::<Result<Vec<_>>>()?,
             })
         })
-        .collect::<Result<Vec<_>>>()?;
+        .collect()
+}
 
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
+    let displays = parse_displays(input)?;
     Ok((part_a(&displays), Some(part_b(&displays)?)))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,6 +227,13 @@ mod tests {
                 "fgae cfgab fg bagce",
             ),
         ];
+        let joined = input
+            .iter()
+            .map(|(patterns, output)| format!("{} | {}", patterns, output))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(unique_digit_counts(&joined)?, [8, 6, 5, 7]);
+
         let displays = input
             .into_iter()
             .map(|(patterns_str, output_str)| {
@@ -195,9 +250,27 @@ mod tests {
             })
             .collect::<Result<Vec<_>>>()?;
 
+        assert_eq!(count_unique_digits(&displays), [8, 6, 5, 7]);
         assert_eq!(part_a(&displays), 26);
         assert_eq!(part_b(&displays)?, 61229);
 
         Ok(())
     }
+
+    #[test]
+    fn test_validate_patterns() -> Result<()> {
+        let input = "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe";
+        assert!(solve(input).is_ok());
+
+        // Remove the pattern for 4, which is the only one with four segments
+        let err = solve(&input.replacen("cgeb ", "", 1)).unwrap_err();
+        assert_eq!(err.to_string(), "Missing pattern for digit 4 (4 segments)");
+
+        let err = solve(&input.replacen("cbdgef ", "fdcge ", 1)).unwrap_err();
+        assert_eq!(err.to_string(), "Too many patterns with 5 segments");
+
+        let err = solve(&input.replacen("cgeb ", "cgeb a ", 1)).unwrap_err();
+        assert_eq!(err.to_string(), "Expected 10 patterns, got 11");
+        Ok(())
+    }
 }
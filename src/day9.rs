@@ -1,17 +1,15 @@
 use anyhow::{anyhow, Result};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::path::Path;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Coordinate {
-    x: isize,
-    y: isize,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Coordinate {
+    pub x: isize,
+    pub y: isize,
 }
 
 impl Coordinate {
-    fn new(x: isize, y: isize) -> Self {
+    pub fn new(x: isize, y: isize) -> Self {
         Self { x, y }
     }
 
@@ -26,55 +24,148 @@ impl Coordinate {
     }
 }
 
-fn part_ab(heightmap: &HashMap<Coordinate, usize>) -> (usize, usize) {
-    // Find the lowest point in every pool and calculate the total risk
-    let mut low_points = Vec::new();
-    let mut risk = 0;
-    for (&c, v) in heightmap.iter() {
-        if c.iter_neighbors()
-            .filter_map(|n| heightmap.get(&n))
-            .all(|n| v < n)
-        {
-            risk += v + 1;
-            low_points.push(c);
+/// Return all points that are lower than their neighbors, sorted by their coordinates. If
+/// `edge_is_wall` is false, points at the edge of the map can never be low points since everything
+/// flows off the map.
+fn low_points(heightmap: &HashMap<Coordinate, usize>, edge_is_wall: bool) -> Vec<Coordinate> {
+    let mut low_points = heightmap
+        .iter()
+        .filter(|(c, v)| {
+            c.iter_neighbors().all(|n| match heightmap.get(&n) {
+                Some(nv) => *v < nv,
+                None => edge_is_wall,
+            })
+        })
+        .map(|(&c, _)| c)
+        .collect::<Vec<_>>();
+    low_points.sort_unstable();
+    low_points
+}
+
+/// Use breadth first flood fill to find all points of the basin around the given low point
+fn fill_basin(
+    heightmap: &HashMap<Coordinate, usize>,
+    low_point: Coordinate,
+) -> HashSet<Coordinate> {
+    let mut queue = VecDeque::new();
+    queue.push_back(low_point);
+
+    let mut visited = HashSet::new();
+    visited.insert(low_point);
+
+    while let Some(c) = queue.pop_front() {
+        for n in c.iter_neighbors() {
+            // Ignore explored coordinates and points with height 9
+            if visited.contains(&n) || heightmap.get(&n).filter(|&nv| *nv < 9).is_none() {
+                continue;
+            }
+            queue.push_back(n);
+            visited.insert(n);
         }
     }
+    visited
+}
+
+/// Everything there is to know about a heightmap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    /// The sum of the risk levels of all low points
+    pub total_risk: usize,
+
+    /// The product of the sizes of the three largest basins
+    pub basin_product: usize,
+
+    /// The basin ID of every point that belongs to a basin, see `basins`
+    pub basins: HashMap<Coordinate, usize>,
+}
+
+/// Analyze the heightmap, filling every basin only once
+pub fn analyze(heightmap: &HashMap<Coordinate, usize>) -> Analysis {
+    analyze_with_edges(heightmap, true)
+}
 
-    // Use breadth first flood fill to find the size of all pools
-    let mut pool_sizes = Vec::new();
+/// Analyze the heightmap, treating the edge of the map either as a wall of height 9 that bounds
+/// basins, or as open. When the edge is open, basins that reach it drain off the map and are left
+/// out, and so are their low points.
+pub fn analyze_with_edges(heightmap: &HashMap<Coordinate, usize>, edge_is_wall: bool) -> Analysis {
+    // Find the lowest point in every pool. Only the low points of the remaining basins are risky
+    let low_points = low_points(heightmap, edge_is_wall);
+    let mut total_risk = 0;
+
+    let mut basins = HashMap::new();
+    let mut basin_sizes = Vec::with_capacity(low_points.len());
     for low_point in low_points {
-        let mut queue = VecDeque::new();
-        queue.push_back(low_point);
-
-        let mut visited = HashSet::new();
-        visited.insert(low_point);
-
-        while let Some(c) = queue.pop_front() {
-            for n in c.iter_neighbors() {
-                // Ignore explored coordinates and points with height 9
-                if visited.contains(&n) || heightmap.get(&n).filter(|&nv| *nv < 9).is_none() {
-                    continue;
-                }
-                queue.push_back(n);
-                visited.insert(n);
-            }
+        let basin = fill_basin(heightmap, low_point);
+        let reaches_edge = || {
+            basin
+                .iter()
+                .any(|c| c.iter_neighbors().any(|n| !heightmap.contains_key(&n)))
+        };
+        if !edge_is_wall && reaches_edge() {
+            continue;
         }
-        pool_sizes.push(visited.len());
+
+        total_risk += heightmap[&low_point] + 1;
+        let id = basin_sizes.len();
+        basin_sizes.push(basin.len());
+        basins.extend(basin.into_iter().map(|c| (c, id)));
     }
-    pool_sizes.sort_unstable();
+    basin_sizes.sort_unstable();
 
-    (
-        risk,
-        pool_sizes.into_iter().rev().take(3).product::<usize>(),
-    )
+    Analysis {
+        total_risk,
+        basin_product: basin_sizes.into_iter().rev().take(3).product(),
+        basins,
+    }
 }
 
-pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+/// Analyze only the part of the heightmap between `min` and `max`, inclusive. Everything outside of
+/// the region is treated as walls, so tiles of a larger map can be analyzed independently
+pub fn analyze_region(
+    heightmap: &HashMap<Coordinate, usize>,
+    min: Coordinate,
+    max: Coordinate,
+) -> Analysis {
+    let region = heightmap
+        .iter()
+        .filter(|(c, _)| (min.x..=max.x).contains(&c.x) && (min.y..=max.y).contains(&c.y))
+        .map(|(&c, &v)| (c, v))
+        .collect();
+    analyze(&region)
+}
+
+/// Map every point that belongs to a basin to the ID of its basin. Basins are numbered in the order
+/// of their low points' coordinates
+pub fn basins(heightmap: &HashMap<Coordinate, usize>) -> HashMap<Coordinate, usize> {
+    analyze(heightmap).basins
+}
+
+/// Find the edge of every basin, which are the points next to a 9 or the edge of the map
+pub fn basin_boundaries(
+    heightmap: &HashMap<Coordinate, usize>,
+) -> HashMap<usize, HashSet<Coordinate>> {
+    let mut boundaries: HashMap<usize, HashSet<Coordinate>> = HashMap::new();
+    for (c, id) in basins(heightmap) {
+        let is_boundary = c
+            .iter_neighbors()
+            .any(|n| heightmap.get(&n).is_none_or(|&nv| nv >= 9));
+        if is_boundary {
+            boundaries.entry(id).or_default().insert(c);
+        }
+    }
+    boundaries
+}
+
+fn part_ab(heightmap: &HashMap<Coordinate, usize>) -> (usize, usize) {
+    let analysis = analyze(heightmap);
+    (analysis.total_risk, analysis.basin_product)
+}
+
+pub fn solve(input: &str) -> Result<(usize, Option<usize>)> {
     let mut heightmap: HashMap<_, usize> = HashMap::new();
 
-    let file = File::open(path)?;
-    for (y, line) in io::BufReader::new(file).lines().enumerate() {
-        for (x, c) in line?.chars().enumerate() {
+    for (y, line) in input.lines().enumerate() {
+        for (x, c) in line.chars().enumerate() {
             heightmap.insert(
                 Coordinate::new(x.try_into()?, y.try_into()?),
                 c.to_digit(10)
@@ -88,31 +179,103 @@ pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
     Ok((a, Some(b)))
 }
 
+pub fn main(path: &Path) -> Result<(usize, Option<usize>)> {
+    solve(&std::fs::read_to_string(path)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_example() -> Result<()> {
-        let map = [
-            [2, 1, 9, 9, 9, 4, 3, 2, 1, 0],
-            [3, 9, 8, 7, 8, 9, 4, 9, 2, 1],
-            [9, 8, 5, 6, 7, 8, 9, 8, 9, 2],
-            [8, 7, 6, 7, 8, 9, 6, 7, 8, 9],
-            [9, 8, 9, 9, 9, 6, 5, 6, 7, 8],
-        ];
-
-        let heightmap = map
-            .into_iter()
+    const EXAMPLE: [[usize; 10]; 5] = [
+        [2, 1, 9, 9, 9, 4, 3, 2, 1, 0],
+        [3, 9, 8, 7, 8, 9, 4, 9, 2, 1],
+        [9, 8, 5, 6, 7, 8, 9, 8, 9, 2],
+        [8, 7, 6, 7, 8, 9, 6, 7, 8, 9],
+        [9, 8, 9, 9, 9, 6, 5, 6, 7, 8],
+    ];
+
+    /// Build a heightmap from rows of heights, with the first row at Y = 0
+    fn heightmap<const W: usize, const H: usize>(
+        rows: [[usize; W]; H],
+    ) -> HashMap<Coordinate, usize> {
+        rows.into_iter()
             .enumerate()
             .flat_map(|(y, row)| {
                 row.into_iter()
                     .enumerate()
                     .map(move |(x, v)| (Coordinate::new(x as isize, y as isize), v))
             })
-            .collect();
+            .collect()
+    }
+
+    #[test]
+    fn test_example() -> Result<()> {
+        let heightmap = heightmap(EXAMPLE);
         assert_eq!(part_ab(&heightmap), (15, 1134));
 
+        let analysis = analyze(&heightmap);
+        assert_eq!((analysis.total_risk, analysis.basin_product), (15, 1134));
+        let ids = analysis.basins.values().collect::<HashSet<_>>();
+        assert_eq!(ids.len(), 4);
+
+        // Every point except the ones with height 9 belongs to a basin
+        assert_eq!(analysis.basins.len(), 50 - 15);
+        assert_eq!(analysis.basins, basins(&heightmap));
+
         Ok(())
     }
+
+    #[test]
+    fn test_analyze_region() {
+        let heightmap = heightmap(EXAMPLE);
+
+        let full = analyze_region(&heightmap, Coordinate::new(0, 0), Coordinate::new(9, 4));
+        assert_eq!(full, analyze(&heightmap));
+
+        // The top left corner only contains a single basin of size 3 with the low point 1
+        let corner = analyze_region(&heightmap, Coordinate::new(0, 0), Coordinate::new(1, 1));
+        assert_eq!((corner.total_risk, corner.basin_product), (2, 3));
+        assert_eq!(corner.basins.len(), 3);
+    }
+
+    #[test]
+    fn test_edge_is_wall() {
+        let heightmap = heightmap([
+            [1, 9, 9, 9, 9],
+            [9, 9, 9, 9, 9],
+            [9, 9, 3, 9, 9],
+            [9, 9, 9, 2, 5],
+            [9, 9, 9, 9, 9],
+        ]);
+
+        // With walls, the corner is a low point and the basin at the right edge is bounded
+        let walled = analyze_with_edges(&heightmap, true);
+        assert_eq!(walled, analyze(&heightmap));
+        assert_eq!(walled.total_risk, 2 + 4 + 3);
+        assert_eq!(walled.basins.values().collect::<HashSet<_>>().len(), 3);
+
+        // With an open edge, the corner drains off the map and so does the basin reaching the edge,
+        // so neither of their low points count towards the risk
+        let open = analyze_with_edges(&heightmap, false);
+        assert_eq!(open.total_risk, 4);
+        assert_eq!(open.basins.len(), 1);
+        assert_eq!(open.basins[&Coordinate::new(2, 2)], 0);
+    }
+
+    #[test]
+    fn test_basin_boundaries() {
+        let heightmap = heightmap([[9, 9, 9, 9], [9, 1, 9, 3], [9, 9, 9, 9]]);
+
+        let boundaries = basin_boundaries(&heightmap);
+        assert_eq!(boundaries.len(), 2);
+        assert_eq!(
+            boundaries[&0],
+            [Coordinate::new(1, 1)].into_iter().collect::<HashSet<_>>()
+        );
+        assert_eq!(
+            boundaries[&1],
+            [Coordinate::new(3, 1)].into_iter().collect::<HashSet<_>>()
+        );
+    }
 }
@@ -0,0 +1,106 @@
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
+/// The server to download puzzle inputs from
+pub const BASE_URL: &str = "https://adventofcode.com";
+
+/// Download the puzzle input for the given day using the session cookie of a logged in user, and
+/// save it to `data/day<num>.txt`
+pub fn fetch_input(day: usize, session: &str) -> Result<String> {
+    let path = format!("data/day{}.txt", day);
+    fetch_input_from(BASE_URL, day, session, path.as_ref())
+}
+
+/// Same as `fetch_input`, but against the given server and saving the input to the given path
+pub fn fetch_input_from(base_url: &str, day: usize, session: &str, path: &Path) -> Result<String> {
+    if !(1..=25).contains(&day) {
+        return Err(anyhow!("Day {} is not a valid day for advent of code", day));
+    }
+
+    let url = format!("{}/2021/day/{}/input", base_url, day);
+    let response = reqwest::blocking::Client::new()
+        .get(&url)
+        .header(reqwest::header::COOKIE, format!("session={}", session))
+        .send()?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Unable to fetch input from {} ({})",
+            url,
+            response.status()
+        ));
+    }
+
+    let input = response.text()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, &input)?;
+    Ok(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serve a single request with the given status and body, returning the URL of the server and
+    /// a handle that resolves to the request line and headers
+    fn serve_once(
+        status: &'static str,
+        body: &'static str,
+    ) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = String::new();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim_end().is_empty() {
+                    break;
+                }
+                request.push_str(&line);
+            }
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+            request
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_fetch_input_from() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("aoc-fetch-{}", std::process::id()));
+        let path = path.join("day3.txt");
+
+        let (url, server) = serve_once("200 OK", "00100\n11110\n");
+        assert_eq!(
+            fetch_input_from(&url, 3, "secret", &path)?,
+            "00100\n11110\n"
+        );
+        assert_eq!(std::fs::read_to_string(&path)?, "00100\n11110\n");
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("GET /2021/day/3/input HTTP/1.1"));
+        assert!(request.to_lowercase().contains("cookie: session=secret"));
+        std::fs::remove_dir_all(path.parent().unwrap())?;
+
+        let (url, server) = serve_once("400 Bad Request", "Please log in");
+        assert!(fetch_input_from(&url, 3, "expired", &path).is_err());
+        assert!(!path.exists());
+        server.join().unwrap();
+
+        assert!(fetch_input_from(&url, 26, "secret", &path).is_err());
+        Ok(())
+    }
+}
@@ -1,3 +1,6 @@
+use anyhow::{anyhow, Result};
+use std::path::Path;
+
 pub mod day1;
 pub mod day10;
 pub mod day11;
@@ -14,9 +17,135 @@ pub mod day20;
 pub mod day21;
 pub mod day22;
 pub mod day23;
+pub mod day24;
+pub mod day25;
 pub mod day3;
+pub mod day4;
 pub mod day5;
 pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+#[cfg(feature = "fetch")]
+pub mod fetch;
+pub mod search;
+pub mod sparse_map;
+
+/// The stringified answers for part A and B
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayResult {
+    pub a: String,
+    pub b: Option<String>,
+}
+
+/// Stringify the answers returned by a day's `solve` function
+impl<A: ToString, B: ToString> From<(A, Option<B>)> for DayResult {
+    fn from((a, b): (A, Option<B>)) -> Self {
+        Self {
+            a: a.to_string(),
+            b: b.map(|answer| answer.to_string()),
+        }
+    }
+}
+
+/// The day number of a solution and the names of its answer types
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayInfo {
+    pub day: usize,
+    pub a_type: &'static str,
+    pub b_type: &'static str,
+}
+
+type Solver<A, B> = fn(&str) -> Result<(A, Option<B>)>;
+
+/// A type that a day's `solve` function can return as an answer
+pub trait AnswerType {
+    /// The name of the type as it's listed by `days`
+    const NAME: &'static str;
+}
+
+macro_rules! answer_types {
+    ($($t:ty),*) => {
+        $(impl AnswerType for $t {
+            const NAME: &'static str = stringify!($t);
+        })*
+    };
+}
+
+answer_types!(isize, usize, u64, u128, String);
+
+fn day_info<A: AnswerType, B: AnswerType>(day: usize, _solve: Solver<A, B>) -> DayInfo {
+    DayInfo {
+        day,
+        a_type: A::NAME,
+        b_type: B::NAME,
+    }
+}
+
+/// Return information about every implemented day, in order
+pub fn days() -> Vec<DayInfo> {
+    vec![
+        day_info(1, day1::solve),
+        day_info(2, day2::solve),
+        day_info(3, day3::solve),
+        day_info(4, day4::solve),
+        day_info(5, day5::solve),
+        day_info(6, day6::solve),
+        day_info(7, day7::solve),
+        day_info(8, day8::solve),
+        day_info(9, day9::solve),
+        day_info(10, day10::solve),
+        day_info(11, day11::solve),
+        day_info(12, day12::solve),
+        day_info(13, day13::solve),
+        day_info(14, day14::solve),
+        day_info(15, day15::solve),
+        day_info(16, day16::solve),
+        day_info(17, day17::solve),
+        day_info(18, day18::solve),
+        day_info(19, day19::solve),
+        day_info(20, day20::solve),
+        day_info(21, day21::solve),
+        day_info(22, day22::solve),
+        day_info(23, day23::solve),
+        day_info(24, day24::solve),
+        day_info(25, day25::solve),
+    ]
+}
+
+/// Solve the given day using the puzzle input as a string
+pub fn solve_str(day: usize, input: &str) -> Result<DayResult> {
+    Ok(match day {
+        1 => day1::solve(input)?.into(),
+        2 => day2::solve(input)?.into(),
+        3 => day3::solve(input)?.into(),
+        4 => day4::solve(input)?.into(),
+        5 => day5::solve(input)?.into(),
+        6 => day6::solve(input)?.into(),
+        7 => day7::solve(input)?.into(),
+        8 => day8::solve(input)?.into(),
+        9 => day9::solve(input)?.into(),
+        10 => day10::solve(input)?.into(),
+        11 => day11::solve(input)?.into(),
+        12 => day12::solve(input)?.into(),
+        13 => day13::solve(input)?.into(),
+        14 => day14::solve(input)?.into(),
+        15 => day15::solve(input)?.into(),
+        16 => day16::solve(input)?.into(),
+        17 => day17::solve(input)?.into(),
+        18 => day18::solve(input)?.into(),
+        19 => day19::solve(input)?.into(),
+        20 => day20::solve(input)?.into(),
+        21 => day21::solve(input)?.into(),
+        22 => day22::solve(input)?.into(),
+        23 => day23::solve(input)?.into(),
+        24 => day24::solve(input)?.into(),
+        25 => day25::solve(input)?.into(),
+        day => return Err(anyhow!("Day {} is not a valid day for advent of code", day)),
+    })
+}
+
+/// Solve the given day using the puzzle input in the given file
+pub fn solve_file(day: usize, path: &Path) -> Result<DayResult> {
+    solve_str(day, &std::fs::read_to_string(path)?)
+}
@@ -1,66 +1,296 @@
+use advent_of_code_2021::{day15, days, solve_str, DayInfo, DayResult};
 use anyhow::{anyhow, Result};
-use std::path::PathBuf;
 use clap::Parser;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Parser)]
 struct Options {
-    /// The day to run the solution for (1-25)
-    day: usize,
+    /// The day to run the solution for (1-25). May be replaced by the input file if it's named
+    /// `day<num>.txt`
+    #[clap(value_name = "DAY")]
+    day_or_input: Option<String>,
 
     /// The input data file. Will look for `data/day<num>.txt` by default
     input: Option<PathBuf>,
+
+    /// Only print the answers, one per line, without the `A:` and `B:` labels
+    #[clap(short, long)]
+    quiet: bool,
+
+    /// Run the solution this many times and print timing statistics to stderr
+    #[clap(long, value_name = "N")]
+    repeat: Option<usize>,
+
+    /// Print a JSON list of all implemented days and the types of their answers, then exit
+    #[clap(long)]
+    list_days: bool,
+
+    /// The position to start from on day 15. Defaults to the top left corner
+    #[clap(long, value_name = "X,Y", parse(try_from_str = parse_position))]
+    start: Option<(isize, isize)>,
+
+    /// The position to end at on day 15. Defaults to the bottom right corner
+    #[clap(long, value_name = "X,Y", parse(try_from_str = parse_position))]
+    end: Option<(isize, isize)>,
+
+    /// The session cookie to use for downloading the input from adventofcode.com if it's missing
+    #[cfg(feature = "fetch")]
+    #[clap(long, value_name = "COOKIE")]
+    session: Option<String>,
+}
+
+fn parse_position(position: &str) -> Result<(isize, isize)> {
+    let (x, y) = position
+        .split_once(',')
+        .ok_or_else(|| anyhow!("{:?} is not a position on the form x,y", position))?;
+    Ok((x.trim().parse()?, y.trim().parse()?))
+}
+
+/// Find the day in a file name such as `data/day16.txt`
+fn day_from_path(path: &Path) -> Option<usize> {
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix("day")?
+        .parse()
+        .ok()
+}
+
+/// Figure out which day to run and where its input is. The first argument is either the day or,
+/// when the day is left out, the input file.
+fn day_and_input(day_or_input: Option<String>, input: Option<PathBuf>) -> Result<(usize, PathBuf)> {
+    let day_or_input = day_or_input
+        .ok_or_else(|| anyhow!("Either a day or an input file named day<num>.txt is required"))?;
+
+    if let Ok(day) = day_or_input.parse::<usize>() {
+        let input = input.unwrap_or_else(|| format!("data/day{}.txt", day).into());
+        return Ok((day, input));
+    }
+
+    if input.is_some() {
+        return Err(anyhow!("{:?} is not a valid day", day_or_input));
+    }
+
+    let input = PathBuf::from(day_or_input);
+    let day = day_from_path(&input).ok_or_else(|| {
+        anyhow!(
+            "Unable to tell the day from {:?}, please specify it explicitly",
+            input
+        )
+    })?;
+    Ok((day, input))
+}
+
+fn manifest_json(days: &[DayInfo]) -> String {
+    let entries = days
+        .iter()
+        .map(|d| json!({"day": d.day, "a_type": d.a_type, "b_type": d.b_type}))
+        .collect();
+    Value::Array(entries).to_string()
 }
 
-fn pad_newlines(answer: String) -> String {
+fn pad_newlines(answer: &str) -> String {
     answer.lines().collect::<Vec<_>>().join("\n   ")
 }
 
-fn as_result<A: ToString, B: ToString>((a, b): (A, Option<B>)) -> (String, Option<String>) {
-    (a.to_string(), b.map(|answer| answer.to_string()))
+fn format_answers(a: &str, b: Option<&str>, quiet: bool) -> String {
+    if quiet {
+        // Multi-line answers are kept as is, except for any trailing newlines
+        return std::iter::once(a)
+            .chain(b)
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+
+    let mut output = format!("A: {}", pad_newlines(a));
+    if let Some(b) = b {
+        output.push_str(&format!("\nB: {}", pad_newlines(b)));
+    }
+    output
+}
+
+/// Run the given solution several times, returning the answers and how long each run took. The
+/// input is only read once so the timings don't include any file IO.
+fn run_repeated(
+    input: &Path,
+    repeat: usize,
+    solve: impl Fn(&str) -> Result<DayResult>,
+) -> Result<(DayResult, Vec<Duration>)> {
+    if repeat == 0 {
+        return Err(anyhow!("The solution must run at least once"));
+    }
+
+    let input = std::fs::read_to_string(input)?;
+
+    let mut answers = None;
+    let mut timings = Vec::with_capacity(repeat);
+    for _ in 0..repeat {
+        let start = Instant::now();
+        answers = Some(solve(&input)?);
+        timings.push(start.elapsed());
+    }
+    Ok((answers.unwrap(), timings))
+}
+
+fn format_timings(timings: &[Duration]) -> String {
+    let mut sorted = timings.to_vec();
+    sorted.sort_unstable();
+
+    let min = sorted.first().copied().unwrap_or_default();
+    let median = sorted.get(sorted.len() / 2).copied().unwrap_or_default();
+    let mean = sorted.iter().sum::<Duration>() / sorted.len().max(1) as u32;
+    format!(
+        "Runs: {}, min: {:?}, median: {:?}, mean: {:?}",
+        sorted.len(),
+        min,
+        median,
+        mean
+    )
 }
 
 fn main() -> Result<()> {
     let opts = Options::parse();
-    let input = opts
-        .input
-        .unwrap_or_else(|| format!("data/day{}.txt", opts.day).into());
-
-    #[allow(
-        overlapping_range_endpoints,
-        unreachable_patterns,
-        clippy::match_overlapping_arm
-    )]
-    let (a, b): (String, Option<String>) = match opts.day {
-        1 => as_result(advent_of_code_2021::day1::main(&input)?),
-        2 => as_result(advent_of_code_2021::day2::main(&input)?),
-        3 => as_result(advent_of_code_2021::day3::main(&input)?),
-        5 => as_result(advent_of_code_2021::day5::main(&input)?),
-        6 => as_result(advent_of_code_2021::day6::main(&input)?),
-        7 => as_result(advent_of_code_2021::day7::main(&input)?),
-        8 => as_result(advent_of_code_2021::day8::main(&input)?),
-        9 => as_result(advent_of_code_2021::day9::main(&input)?),
-        10 => as_result(advent_of_code_2021::day10::main(&input)?),
-        11 => as_result(advent_of_code_2021::day11::main(&input)?),
-        12 => as_result(advent_of_code_2021::day12::main(&input)?),
-        13 => as_result(advent_of_code_2021::day13::main(&input)?),
-        14 => as_result(advent_of_code_2021::day14::main(&input)?),
-        15 => as_result(advent_of_code_2021::day15::main(&input)?),
-        16 => as_result(advent_of_code_2021::day16::main(&input)?),
-        17 => as_result(advent_of_code_2021::day17::main(&input)?),
-        18 => as_result(advent_of_code_2021::day18::main(&input)?),
-        19 => as_result(advent_of_code_2021::day19::main(&input)?),
-        20 => as_result(advent_of_code_2021::day20::main(&input)?),
-        21 => as_result(advent_of_code_2021::day21::main(&input)?),
-        22 => as_result(advent_of_code_2021::day22::main(&input)?),
-        23 => as_result(advent_of_code_2021::day23::main(&input)?),
-        1..=25 => return Err(anyhow!("No implementation for this day yet")),
-        day => return Err(anyhow!("Day {} is not a valid day for advent of code", day)),
+    if opts.list_days {
+        println!("{}", manifest_json(&days()));
+        return Ok(());
+    }
+
+    let (day, input) = day_and_input(opts.day_or_input, opts.input)?;
+
+    #[cfg(feature = "fetch")]
+    if let Some(session) = &opts.session {
+        use advent_of_code_2021::fetch::{fetch_input_from, BASE_URL};
+        if !input.exists() {
+            fetch_input_from(BASE_URL, day, session, &input)?;
+        }
+    }
+
+    let (start, end) = (opts.start, opts.end);
+    if day != 15 && (start.is_some() || end.is_some()) {
+        return Err(anyhow!("--start and --end can only be used for day 15"));
+    }
+
+    let repeat = opts.repeat.unwrap_or(1);
+    let (DayResult { a, b }, timings) = if day == 15 {
+        run_repeated(&input, repeat, |input| {
+            let coordinate = |(x, y)| day15::Coordinate::new(x, y);
+            Ok(day15::solve_between(input, start.map(coordinate), end.map(coordinate))?.into())
+        })?
+    } else {
+        run_repeated(&input, repeat, |input| solve_str(day, input))?
     };
 
-    println!("A: {}", pad_newlines(a));
-    if let Some(b) = b {
-        println!("B: {}", pad_newlines(b));
+    println!("{}", format_answers(&a, b.as_deref(), opts.quiet));
+    if opts.repeat.is_some() {
+        eprintln!("{}", format_timings(&timings));
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_output() -> Result<()> {
+        let DayResult { a, b } = solve_str(1, &std::fs::read_to_string("data/day1.txt")?)?;
+        assert_eq!(format_answers(&a, b.as_deref(), true), "1676\n1706");
+
+        let DayResult { a, b } = solve_str(13, &std::fs::read_to_string("data/day13.txt")?)?;
+        let output = format_answers(&a, b.as_deref(), true);
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 7);
+        assert_eq!(lines[0], "747");
+        assert_eq!(lines[1], " ##  ###  #  # #### ###   ##  #  # #  #");
+        assert_eq!(lines[6], "#  # #  # #  # #### #     ##   ##  #  #");
+        Ok(())
+    }
+
+    #[test]
+    fn test_labeled_output() {
+        assert_eq!(format_answers("1", None, false), "A: 1");
+        assert_eq!(format_answers("1", Some("2"), false), "A: 1\nB: 2");
+        assert_eq!(
+            format_answers("1", Some("a\nb\n"), false),
+            "A: 1\nB: a\n   b"
+        );
+    }
+
+    #[test]
+    fn test_repeat() -> Result<()> {
+        let solve = |input: &str| solve_str(1, input);
+        let (DayResult { a, b }, timings) = run_repeated(Path::new("data/day1.txt"), 3, solve)?;
+        assert_eq!(format_answers(&a, b.as_deref(), false), "A: 1676\nB: 1706");
+        assert_eq!(timings.len(), 3);
+        assert!(run_repeated(Path::new("data/day1.txt"), 0, solve).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_timings() {
+        let timings = [
+            Duration::from_millis(3),
+            Duration::from_millis(1),
+            Duration::from_millis(8),
+        ];
+        assert_eq!(
+            format_timings(&timings),
+            "Runs: 3, min: 1ms, median: 3ms, mean: 4ms"
+        );
+    }
+
+    #[test]
+    fn test_parse_position() -> Result<()> {
+        assert_eq!(parse_position("3,-4")?, (3, -4));
+        assert_eq!(parse_position("3, 4")?, (3, 4));
+        assert!(parse_position("3").is_err());
+        assert!(parse_position("3,x").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_day_from_path() {
+        assert_eq!(day_from_path(Path::new("data/day16.txt")), Some(16));
+        assert_eq!(day_from_path(Path::new("day3")), Some(3));
+        assert_eq!(day_from_path(Path::new("data/input.txt")), None);
+        assert_eq!(day_from_path(Path::new("data/dayx.txt")), None);
+    }
+
+    #[test]
+    fn test_day_and_input() -> Result<()> {
+        assert_eq!(
+            day_and_input(Some("16".to_owned()), None)?,
+            (16, "data/day16.txt".into())
+        );
+        assert_eq!(
+            day_and_input(Some("2".to_owned()), Some("other.txt".into()))?,
+            (2, "other.txt".into())
+        );
+        assert_eq!(
+            day_and_input(Some("data/day16.txt".to_owned()), None)?,
+            (16, "data/day16.txt".into())
+        );
+        assert!(day_and_input(Some("input.txt".to_owned()), None).is_err());
+        assert!(day_and_input(None, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_manifest_json() -> Result<()> {
+        let manifest: Value = serde_json::from_str(&manifest_json(&days()))?;
+        assert_eq!(manifest.as_array().map(Vec::len), Some(25));
+        assert_eq!(
+            manifest[0],
+            json!({"day": 1, "a_type": "usize", "b_type": "usize"})
+        );
+        assert_eq!(
+            manifest[12],
+            json!({"day": 13, "a_type": "usize", "b_type": "String"})
+        );
+        assert_eq!(manifest_json(&[]), "[]");
+        Ok(())
+    }
+}
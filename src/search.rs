@@ -0,0 +1,217 @@
+use anyhow::{anyhow, Result};
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+use std::ops::Add;
+
+/// We need this exotic data structure since we can't store types that don't implement Ord directly
+/// in a BinaryHeap
+#[derive(Debug, Clone)]
+struct PriorityQueue<P, T> {
+    heap: BinaryHeap<(P, Reverse<usize>)>,
+    values: HashMap<usize, T>,
+    next_index: usize,
+}
+
+impl<P, T> PriorityQueue<P, T>
+where
+    P: Ord,
+{
+    fn new() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            values: HashMap::new(),
+            next_index: 0,
+        }
+    }
+
+    fn push(&mut self, v: T, p: P) {
+        self.heap.push((p, Reverse(self.next_index)));
+        self.values.insert(self.next_index, v);
+        self.next_index += 1;
+    }
+
+    fn pop(&mut self) -> Option<(T, P)> {
+        self.heap
+            .pop()
+            .map(|(p, Reverse(k))| (self.values.remove(&k).unwrap(), p))
+    }
+}
+
+/// Find the cost of the cheapest path from `start` to a node for which `is_goal` is true
+pub fn dijkstra<N, C>(
+    start: N,
+    is_goal: impl Fn(&N) -> bool,
+    neighbors: impl Fn(&N) -> Vec<(N, C)>,
+) -> Option<C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+{
+    a_star(start, is_goal, neighbors, |_| C::default()).map(|(cost, _)| cost)
+}
+
+/// Find the cost of the cheapest path from `start` to every reachable node
+pub fn dijkstra_all<N, C>(start: N, neighbors: impl Fn(&N) -> Vec<(N, C)>) -> HashMap<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+{
+    let mut lowest_cost = HashMap::new();
+    lowest_cost.insert(start.clone(), C::default());
+
+    let mut queue = PriorityQueue::new();
+    queue.push((start, C::default()), Reverse(C::default()));
+
+    while let Some(((node, cost), _)) = queue.pop() {
+        // Skip nodes that we have already found a cheaper way to
+        if lowest_cost.get(&node).is_some_and(|&c| c < cost) {
+            continue;
+        }
+
+        for (neighbor, step_cost) in neighbors(&node) {
+            let neighbor_cost = cost + step_cost;
+            match lowest_cost.entry(neighbor.clone()) {
+                Entry::Occupied(e) if *e.get() <= neighbor_cost => continue,
+                Entry::Occupied(mut e) => {
+                    e.insert(neighbor_cost);
+                }
+                Entry::Vacant(e) => {
+                    e.insert(neighbor_cost);
+                }
+            }
+            queue.push((neighbor, neighbor_cost), Reverse(neighbor_cost));
+        }
+    }
+    lowest_cost
+}
+
+/// Find the cost of the cheapest path from `start` to a node for which `is_goal` is true, using
+/// `estimate` to explore the most promising nodes first. Returns the cost and the number of
+/// expanded nodes.
+///
+/// The estimate must never be larger than the actual remaining cost, and it must not decrease by
+/// more than the cost of any step, or the returned cost may not be the lowest one.
+pub fn a_star<N, C>(
+    start: N,
+    is_goal: impl Fn(&N) -> bool,
+    neighbors: impl Fn(&N) -> Vec<(N, C)>,
+    estimate: impl Fn(&N) -> C,
+) -> Option<(C, usize)>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+{
+    // Without a limit the search can't fail
+    a_star_limited(start, is_goal, neighbors, estimate, None).unwrap_or(None)
+}
+
+/// Same as `a_star`, but gives up with an error once more than `max_states` nodes have been
+/// expanded. `None` means that the search may run forever.
+pub fn a_star_limited<N, C>(
+    start: N,
+    is_goal: impl Fn(&N) -> bool,
+    neighbors: impl Fn(&N) -> Vec<(N, C)>,
+    estimate: impl Fn(&N) -> C,
+    max_states: Option<usize>,
+) -> Result<Option<(C, usize)>>
+where
+    N: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+{
+    let mut lowest_cost = HashMap::new();
+    lowest_cost.insert(start.clone(), C::default());
+
+    let mut queue = PriorityQueue::new();
+    let initial_estimate = estimate(&start);
+    queue.push((start, C::default()), Reverse(initial_estimate));
+
+    let mut num_expanded = 0;
+    while let Some(((node, cost), _)) = queue.pop() {
+        // Skip nodes that we have already found a cheaper way to
+        if lowest_cost.get(&node).is_some_and(|&c| c < cost) {
+            continue;
+        }
+        if is_goal(&node) {
+            return Ok(Some((cost, num_expanded)));
+        }
+        if max_states.is_some_and(|m| num_expanded >= m) {
+            return Err(anyhow!("Search exceeded {} states", num_expanded));
+        }
+        num_expanded += 1;
+
+        for (neighbor, step_cost) in neighbors(&node) {
+            let neighbor_cost = cost + step_cost;
+            match lowest_cost.entry(neighbor.clone()) {
+                Entry::Occupied(e) if *e.get() <= neighbor_cost => continue,
+                Entry::Occupied(mut e) => {
+                    e.insert(neighbor_cost);
+                }
+                Entry::Vacant(e) => {
+                    e.insert(neighbor_cost);
+                }
+            }
+            let neighbor_estimate = neighbor_cost + estimate(&neighbor);
+            queue.push((neighbor, neighbor_cost), Reverse(neighbor_estimate));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> HashMap<char, Vec<(char, usize)>> {
+        let mut graph = HashMap::new();
+        graph.insert('a', vec![('b', 7), ('c', 9), ('f', 14)]);
+        graph.insert('b', vec![('a', 7), ('c', 10), ('d', 15)]);
+        graph.insert('c', vec![('a', 9), ('b', 10), ('d', 11), ('f', 2)]);
+        graph.insert('d', vec![('b', 15), ('c', 11), ('e', 6)]);
+        graph.insert('e', vec![('d', 6), ('f', 9)]);
+        graph.insert('f', vec![('a', 14), ('c', 2), ('e', 9)]);
+        graph.insert('g', vec![]);
+        graph
+    }
+
+    #[test]
+    fn test_dijkstra() {
+        let graph = graph();
+        let neighbors = |n: &char| graph[n].clone();
+        assert_eq!(dijkstra('a', |&n| n == 'a', neighbors), Some(0));
+        assert_eq!(dijkstra('a', |&n| n == 'e', neighbors), Some(20));
+        assert_eq!(dijkstra('a', |&n| n == 'd', neighbors), Some(20));
+        assert_eq!(dijkstra('b', |&n| n == 'f', neighbors), Some(12));
+        assert_eq!(dijkstra('a', |&n| n == 'g', neighbors), None);
+    }
+
+    #[test]
+    fn test_dijkstra_all() {
+        let graph = graph();
+        let costs = dijkstra_all('a', |n| graph[n].clone());
+        assert_eq!(costs.len(), 6);
+        for (&n, &cost) in &costs {
+            assert_eq!(dijkstra('a', |&m| m == n, |m| graph[m].clone()), Some(cost));
+        }
+        assert!(!costs.contains_key(&'g'));
+    }
+
+    #[test]
+    fn test_a_star() -> Result<()> {
+        // Walk along a line where the distance to the goal is a perfect estimate
+        let neighbors = |&n: &isize| vec![(n - 1, 1), (n + 1, 1)];
+        let estimate = |&n: &isize| (10 - n).unsigned_abs();
+
+        let (cost, num_expanded) = a_star(0isize, |&n| n == 10, neighbors, estimate).unwrap();
+        assert_eq!(cost, 10);
+        assert_eq!(num_expanded, 10);
+
+        let limited =
+            |max_states| a_star_limited(0isize, |&n| n == 10, neighbors, estimate, max_states);
+        assert_eq!(limited(Some(10))?, Some((10, 10)));
+        assert!(limited(Some(9)).is_err());
+        assert_eq!(limited(None)?, Some((10, 10)));
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+pub type Point = (isize, isize);
+
+/// A type that can be used as a point in a `SparseMap`
+pub trait MapPoint: Copy + Eq + Hash {
+    fn from_xy(x: isize, y: isize) -> Self;
+    fn x(&self) -> isize;
+    fn y(&self) -> isize;
+}
+
+impl MapPoint for Point {
+    fn from_xy(x: isize, y: isize) -> Self {
+        (x, y)
+    }
+
+    fn x(&self) -> isize {
+        self.0
+    }
+
+    fn y(&self) -> isize {
+        self.1
+    }
+}
+
+/// A 2D map where only some of the points have a value. The bounds of the points are kept up to
+/// date on every change so they never have to be computed by scanning all points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseMap<T, P: MapPoint = Point> {
+    cells: HashMap<P, T>,
+    bounds: Option<(P, P)>,
+}
+
+impl<T, P: MapPoint> SparseMap<T, P> {
+    pub fn new() -> Self {
+        Self {
+            cells: HashMap::new(),
+            bounds: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// The top left and bottom right corner of the smallest rectangle containing all points, or
+    /// `None` if the map is empty
+    pub fn bounds(&self) -> Option<(P, P)> {
+        self.bounds
+    }
+
+    pub fn get(&self, p: P) -> Option<&T> {
+        self.cells.get(&p)
+    }
+
+    pub fn contains(&self, p: P) -> bool {
+        self.cells.contains_key(&p)
+    }
+
+    pub fn insert(&mut self, p: P, value: T) -> Option<T> {
+        self.bounds = Some(match self.bounds {
+            Some((min, max)) => (
+                P::from_xy(min.x().min(p.x()), min.y().min(p.y())),
+                P::from_xy(max.x().max(p.x()), max.y().max(p.y())),
+            ),
+            None => (p, p),
+        });
+        self.cells.insert(p, value)
+    }
+
+    pub fn remove(&mut self, p: P) -> Option<T> {
+        let value = self.cells.remove(&p)?;
+
+        // The bounds only change if the point was on the edge, in which case we have to scan
+        if let Some((min, max)) = self.bounds {
+            if p.x() == min.x() || p.x() == max.x() || p.y() == min.y() || p.y() == max.y() {
+                self.bounds = Self::compute_bounds(&self.cells);
+            }
+        }
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (P, &T)> {
+        self.cells.iter().map(|(&p, v)| (p, v))
+    }
+
+    /// Iterate over the points above, to the right, below and to the left of the given point that
+    /// exist in the map
+    pub fn neighbors(&self, p: P) -> impl Iterator<Item = (P, &T)> {
+        let (x, y) = (p.x(), p.y());
+        [(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)]
+            .into_iter()
+            .map(|(x, y)| P::from_xy(x, y))
+            .filter_map(move |n| self.cells.get(&n).map(|v| (n, v)))
+    }
+
+    fn compute_bounds(cells: &HashMap<P, T>) -> Option<(P, P)> {
+        let min_x = cells.keys().map(P::x).min()?;
+        let max_x = cells.keys().map(P::x).max()?;
+        let min_y = cells.keys().map(P::y).min()?;
+        let max_y = cells.keys().map(P::y).max()?;
+        Some((P::from_xy(min_x, min_y), P::from_xy(max_x, max_y)))
+    }
+}
+
+impl<T, P: MapPoint> Default for SparseMap<T, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, P: MapPoint> FromIterator<(P, T)> for SparseMap<T, P> {
+    fn from_iter<I: IntoIterator<Item = (P, T)>>(iter: I) -> Self {
+        let cells = iter.into_iter().collect::<HashMap<_, _>>();
+        let bounds = Self::compute_bounds(&cells);
+        Self { cells, bounds }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounds() {
+        let mut map = SparseMap::new();
+        assert_eq!(map.bounds(), None);
+
+        map.insert((2, 3), 'a');
+        assert_eq!(map.bounds(), Some(((2, 3), (2, 3))));
+        map.insert((-1, 5), 'b');
+        map.insert((4, 4), 'c');
+        assert_eq!(map.bounds(), Some(((-1, 3), (4, 5))));
+
+        // Removing a point inside the bounds leaves them as is
+        map.insert((0, 4), 'd');
+        assert_eq!(map.remove((0, 4)), Some('d'));
+        assert_eq!(map.bounds(), Some(((-1, 3), (4, 5))));
+
+        // Removing a point on the edge shrinks the bounds
+        assert_eq!(map.remove((-1, 5)), Some('b'));
+        assert_eq!(map.bounds(), Some(((2, 3), (4, 4))));
+        assert_eq!(map.remove((-1, 5)), None);
+
+        map.remove((2, 3));
+        map.remove((4, 4));
+        assert!(map.is_empty());
+        assert_eq!(map.bounds(), None);
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let map = [((0, 0), 1), ((1, 0), 2), ((0, 1), 3), ((1, 1), 4)]
+            .into_iter()
+            .collect::<SparseMap<_>>();
+        assert_eq!(map.bounds(), Some(((0, 0), (1, 1))));
+
+        let mut neighbors = map.neighbors((0, 0)).collect::<Vec<_>>();
+        neighbors.sort_unstable();
+        assert_eq!(neighbors, vec![((0, 1), &3), ((1, 0), &2)]);
+        assert_eq!(map.neighbors((5, 5)).count(), 0);
+    }
+}
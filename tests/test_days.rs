@@ -1,10 +1,63 @@
+use advent_of_code_2021::DayResult;
 use anyhow::Result;
 use std::path::Path;
 
-fn run_day<A, B>(day: usize, f: fn(&Path) -> Result<(A, Option<B>)>) -> Result<(A, Option<B>)> {
+type Solver<A, B> = fn(&Path) -> Result<(A, Option<B>)>;
+
+fn run_day<A, B>(day: usize, f: Solver<A, B>) -> Result<(A, Option<B>)> {
     f(format!("data/day{}.txt", day).as_ref())
 }
 
+#[test]
+fn test_solve_str() -> Result<()> {
+    let expected = [
+        (1, "1676", "1706"),
+        (2, "1488669", "1176514794"),
+        (3, "3958484", "1613181"),
+    ];
+    for (day, a, b) in expected {
+        let input = std::fs::read_to_string(format!("data/day{}.txt", day))?;
+        assert_eq!(
+            advent_of_code_2021::solve_str(day, &input)?,
+            DayResult {
+                a: a.to_string(),
+                b: Some(b.to_string())
+            }
+        );
+    }
+    assert!(advent_of_code_2021::solve_str(26, "").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_solve_file() -> Result<()> {
+    assert_eq!(
+        advent_of_code_2021::solve_file(1, Path::new("data/day1.txt"))?,
+        DayResult {
+            a: "1676".to_string(),
+            b: Some("1706".to_string())
+        }
+    );
+    assert!(advent_of_code_2021::solve_file(1, Path::new("data/missing.txt")).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_days() {
+    let days = advent_of_code_2021::days();
+    assert_eq!(days.len(), 25);
+    assert!(days.iter().enumerate().all(|(i, d)| d.day == i + 1));
+    assert_eq!(
+        days[0],
+        advent_of_code_2021::DayInfo {
+            day: 1,
+            a_type: "usize",
+            b_type: "usize",
+        }
+    );
+    assert_eq!(days[15].b_type, "u128");
+}
+
 #[test]
 fn test_day1() -> Result<()> {
     assert_eq!(
@@ -101,6 +154,11 @@ fn test_day12() -> Result<()> {
         run_day(12, advent_of_code_2021::day12::main)?,
         (4912, Some(150004))
     );
+
+    // Large caves that are connected to each other would make the solver recurse forever
+    let input = std::fs::read_to_string("tests/data/day12_large_cycle.txt")?;
+    let err = advent_of_code_2021::solve_str(12, &input).unwrap_err();
+    assert!(err.to_string().contains("Two large caves are connected"));
     Ok(())
 }
 
@@ -192,3 +250,13 @@ fn test_day22() -> Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn test_day24() -> Result<()> {
+    // The puzzle input isn't checked in, so use a program with the same structure instead
+    assert_eq!(
+        advent_of_code_2021::day24::main(Path::new("tests/data/day24_monad.txt"))?,
+        (99_394_899_891_971, Some(92_171_126_131_911))
+    );
+    Ok(())
+}